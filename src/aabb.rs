@@ -0,0 +1,75 @@
+use crate::vec3::{Vec3, Point3D};
+use crate::ray::Ray;
+
+/// An axis-aligned bounding box, between the corners `minimum` and `maximum`.
+/// Unbounded objects, like planes, have infinite corners.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub minimum: Point3D,
+    pub maximum: Point3D
+}
+
+impl Aabb {
+    /// A box that contains everything.
+    pub fn infinite() -> Self {
+        Aabb { minimum: Vec3(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY), maximum: Vec3(f32::INFINITY, f32::INFINITY, f32::INFINITY) }
+    }
+
+    /// The box of all points within `radius` of `center`.
+    pub fn around(center: Point3D, radius: Vec3) -> Self {
+        Aabb { minimum: center - radius, maximum: center + radius }
+    }
+
+    /// The smallest box containing both `box0` and `box1`.
+    pub fn surrounding_box(box0: Aabb, box1: Aabb) -> Self {
+        Aabb {
+            minimum: Vec3(box0.minimum.x().min(box1.minimum.x()), box0.minimum.y().min(box1.minimum.y()), box0.minimum.z().min(box1.minimum.z())),
+            maximum: Vec3(box0.maximum.x().max(box1.maximum.x()), box0.maximum.y().max(box1.maximum.y()), box0.maximum.z().max(box1.maximum.z()))
+        }
+    }
+
+    /// The box of the points in both `box0` and `box1`. It is empty, with
+    /// `minimum` above `maximum`, when they do not overlap.
+    pub fn overlap(box0: Aabb, box1: Aabb) -> Self {
+        Aabb {
+            minimum: Vec3(box0.minimum.x().max(box1.minimum.x()), box0.minimum.y().max(box1.minimum.y()), box0.minimum.z().max(box1.minimum.z())),
+            maximum: Vec3(box0.maximum.x().min(box1.maximum.x()), box0.maximum.y().min(box1.maximum.y()), box0.maximum.z().min(box1.maximum.z()))
+        }
+    }
+
+    /// The box of a disk of `radius` around `center`, facing the unit `normal`.
+    pub fn disk(center: Point3D, normal: Vec3, radius: f32) -> Self {
+        let extent = |n: f32| radius * f32::sqrt((1.0 - n * n).max(0.0));
+        Aabb::around(center, Vec3(extent(normal.x()), extent(normal.y()), extent(normal.z())))
+    }
+
+    /// The part of `r` inside the box, as the range of `t` within
+    /// [`t_min`, `t_max`], with the slab method. `None` if `r` misses the box.
+    pub fn clip(&self, r: Ray, t_min: f32, t_max: f32) -> Option<(f32, f32)> {
+        let (mut t_min, mut t_max) = (t_min, t_max);
+        let slabs = [
+            (r.origin().x(), r.direction().x(), self.minimum.x(), self.maximum.x()),
+            (r.origin().y(), r.direction().y(), self.minimum.y(), self.maximum.y()),
+            (r.origin().z(), r.direction().z(), self.minimum.z(), self.maximum.z())
+        ];
+        for (origin, direction, minimum, maximum) in slabs {
+            let inv_d = 1.0 / direction;
+            let mut t0 = (minimum - origin) * inv_d;
+            let mut t1 = (maximum - origin) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            // NaN, from an infinite slab seen edge on, leaves the range as is.
+            if t0 > t_min {
+                t_min = t0;
+            }
+            if t1 < t_max {
+                t_max = t1;
+            }
+            if t_max <= t_min {
+                return None;
+            }
+        }
+        Some((t_min, t_max))
+    }
+}
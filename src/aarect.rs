@@ -0,0 +1,164 @@
+use crate::vec3::{self, Vec3, Point3D};
+use crate::ray::Ray;
+use crate::hit::{HitRecord, Hittable};
+use crate::aabb::Aabb;
+use crate::material::Material;
+
+/// A rectangle in the plane z = k, spanning [x0, x1] x [y0, y1].
+pub struct XyRect {
+    pub x0: f32,
+    pub x1: f32,
+    pub y0: f32,
+    pub y1: f32,
+    pub k: f32,
+    pub material: Box<dyn Material>
+}
+
+/// A rectangle in the plane y = k, spanning [x0, x1] x [z0, z1].
+pub struct XzRect {
+    pub x0: f32,
+    pub x1: f32,
+    pub z0: f32,
+    pub z1: f32,
+    pub k: f32,
+    pub material: Box<dyn Material>
+}
+
+/// A rectangle in the plane x = k, spanning [y0, y1] x [z0, z1].
+pub struct YzRect {
+    pub y0: f32,
+    pub y1: f32,
+    pub z0: f32,
+    pub z1: f32,
+    pub k: f32,
+    pub material: Box<dyn Material>
+}
+
+/// Density, with respect to solid angle, of picking a point uniformly on a
+/// flat shape of `area` and hitting it at `record_t` along direction `v`.
+pub fn area_pdf_value(v: Vec3, record_t: f32, outward_normal: Vec3, area: f32) -> f32 {
+    let distance_squared = record_t * record_t * v.length_squared();
+    let cosine = f32::abs(v.dot(outward_normal) / v.length());
+
+    distance_squared / (cosine * area)
+}
+
+impl Hittable for XyRect {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let t = (self.k - r.origin().z()) / r.direction().z();
+        if t < t_min || t > t_max {
+            return None;
+        }
+        let x = r.origin().x() + t * r.direction().x();
+        let y = r.origin().y() + t * r.direction().y();
+        if x < self.x0 || x > self.x1 || y < self.y0 || y > self.y1 {
+            return None;
+        }
+        let u = (x - self.x0) / (self.x1 - self.x0);
+        let v = (y - self.y0) / (self.y1 - self.y0);
+
+        Some(HitRecord::new(r, t, Vec3(0.0, 0.0, 1.0), u, v, Vec3(1.0, 0.0, 0.0), &*self.material))
+    }
+
+    /// The box is padded, so that it is not flat.
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        Some(Aabb { minimum: Vec3(self.x0, self.y0, self.k - 0.0001), maximum: Vec3(self.x1, self.y1, self.k + 0.0001) })
+    }
+
+    fn pdf_value(&self, origin: Point3D, v: Vec3) -> f32 {
+        match self.hit(Ray(origin, v, 0.0, 0.0), 0.001, f32::INFINITY) {
+            Some(record) => {
+                let area = (self.x1 - self.x0) * (self.y1 - self.y0);
+                area_pdf_value(v, record.t, Vec3(0.0, 0.0, 1.0), area)
+            }
+            None => 0.0
+        }
+    }
+
+    fn random(&self, origin: Point3D) -> Vec3 {
+        let random_point = Vec3(vec3::random_double(Some(self.x0), Some(self.x1)),
+                                vec3::random_double(Some(self.y0), Some(self.y1)),
+                                self.k);
+        random_point - origin
+    }
+}
+
+impl Hittable for XzRect {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let t = (self.k - r.origin().y()) / r.direction().y();
+        if t < t_min || t > t_max {
+            return None;
+        }
+        let x = r.origin().x() + t * r.direction().x();
+        let z = r.origin().z() + t * r.direction().z();
+        if x < self.x0 || x > self.x1 || z < self.z0 || z > self.z1 {
+            return None;
+        }
+        let u = (x - self.x0) / (self.x1 - self.x0);
+        let v = (z - self.z0) / (self.z1 - self.z0);
+
+        Some(HitRecord::new(r, t, Vec3(0.0, 1.0, 0.0), u, v, Vec3(1.0, 0.0, 0.0), &*self.material))
+    }
+
+    /// The box is padded, so that it is not flat.
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        Some(Aabb { minimum: Vec3(self.x0, self.k - 0.0001, self.z0), maximum: Vec3(self.x1, self.k + 0.0001, self.z1) })
+    }
+
+    fn pdf_value(&self, origin: Point3D, v: Vec3) -> f32 {
+        match self.hit(Ray(origin, v, 0.0, 0.0), 0.001, f32::INFINITY) {
+            Some(record) => {
+                let area = (self.x1 - self.x0) * (self.z1 - self.z0);
+                area_pdf_value(v, record.t, Vec3(0.0, 1.0, 0.0), area)
+            }
+            None => 0.0
+        }
+    }
+
+    fn random(&self, origin: Point3D) -> Vec3 {
+        let random_point = Vec3(vec3::random_double(Some(self.x0), Some(self.x1)),
+                                self.k,
+                                vec3::random_double(Some(self.z0), Some(self.z1)));
+        random_point - origin
+    }
+}
+
+impl Hittable for YzRect {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let t = (self.k - r.origin().x()) / r.direction().x();
+        if t < t_min || t > t_max {
+            return None;
+        }
+        let y = r.origin().y() + t * r.direction().y();
+        let z = r.origin().z() + t * r.direction().z();
+        if y < self.y0 || y > self.y1 || z < self.z0 || z > self.z1 {
+            return None;
+        }
+        let u = (y - self.y0) / (self.y1 - self.y0);
+        let v = (z - self.z0) / (self.z1 - self.z0);
+
+        Some(HitRecord::new(r, t, Vec3(1.0, 0.0, 0.0), u, v, Vec3(0.0, 1.0, 0.0), &*self.material))
+    }
+
+    /// The box is padded, so that it is not flat.
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        Some(Aabb { minimum: Vec3(self.k - 0.0001, self.y0, self.z0), maximum: Vec3(self.k + 0.0001, self.y1, self.z1) })
+    }
+
+    fn pdf_value(&self, origin: Point3D, v: Vec3) -> f32 {
+        match self.hit(Ray(origin, v, 0.0, 0.0), 0.001, f32::INFINITY) {
+            Some(record) => {
+                let area = (self.y1 - self.y0) * (self.z1 - self.z0);
+                area_pdf_value(v, record.t, Vec3(1.0, 0.0, 0.0), area)
+            }
+            None => 0.0
+        }
+    }
+
+    fn random(&self, origin: Point3D) -> Vec3 {
+        let random_point = Vec3(self.k,
+                                vec3::random_double(Some(self.y0), Some(self.y1)),
+                                vec3::random_double(Some(self.z0), Some(self.z1)));
+        random_point - origin
+    }
+}
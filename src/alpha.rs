@@ -0,0 +1,58 @@
+use crate::vec3::{Vec3, Point3D};
+use crate::ray::Ray;
+use crate::hit::{HitRecord, Hittable};
+use crate::texture::Texture;
+use crate::aabb::Aabb;
+use rand::random;
+
+/// How the opacity of an `AlphaMasked` hittable is used.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AlphaMode {
+    /// Hits where the opacity is below the threshold are skipped, for sharp
+    /// cutouts like leaves and fences.
+    Cutout(f32),
+    /// Hits are kept with a probability equal to the opacity, so that
+    /// partially transparent surfaces, like decals, average out over samples.
+    Stochastic
+}
+
+/// An `object` with holes: its opacity is read at each hit from the first
+/// channel of the `alpha` texture, and rays go through where it is
+/// transparent. Camera rays, bounces and shadow rays all see the same holes.
+pub struct AlphaMasked {
+    pub object: Box<dyn Hittable>,
+    pub alpha: Box<dyn Texture>,
+    pub mode: AlphaMode
+}
+
+impl Hittable for AlphaMasked {
+    /// Skipped hits are retried further along the ray, so that the back of a
+    /// closed object can be seen through a hole in its front.
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let mut t_min = t_min;
+        loop {
+            let record = self.object.hit(r, t_min, t_max)?;
+            let alpha = self.alpha.value(record.u, record.v, record.p).x();
+            let opaque = match self.mode {
+                AlphaMode::Cutout(threshold) => alpha >= threshold,
+                AlphaMode::Stochastic => random::<f32>() < alpha
+            };
+            if opaque {
+                return Some(record);
+            }
+            t_min = record.t + 1e-4;
+        }
+    }
+
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb> {
+        self.object.bounding_box(time0, time1)
+    }
+
+    fn pdf_value(&self, origin: Point3D, v: Vec3) -> f32 {
+        self.object.pdf_value(origin, v)
+    }
+
+    fn random(&self, origin: Point3D) -> Vec3 {
+        self.object.random(origin)
+    }
+}
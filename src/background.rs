@@ -0,0 +1,148 @@
+use rand::random;
+use crate::vec3::{self, Vec3, Color};
+use crate::ray::Ray;
+use crate::texture::{Texture, ImageTexture};
+use crate::sphere::get_sphere_uv;
+
+/// What a ray sees when it escapes the scene.
+///
+/// A background that is not black is an environment light: it surrounds the
+/// scene and is sampled uniformly over the sphere of directions, or by
+/// brightness for environment maps.
+pub enum Background {
+    /// Blend of white and blue, depending on the height of the ray direction.
+    Sky,
+    Solid(Color),
+    Environment(EnvironmentMap)
+}
+
+/// An equirectangular image all around the scene, with its top row straight
+/// up. Columns follow the u of `get_sphere_uv`, so that the image wraps the
+/// scene as it would a sphere. Its colors are multiplied by `scale`, since
+/// image pixels stop at 1.
+///
+/// Directions are sampled in proportion to the brightness of the pixels,
+/// weighted by the solid angle they cover, so that a small bright sun is
+/// found by light sampling.
+pub struct EnvironmentMap {
+    image: ImageTexture,
+    scale: f32,
+    /// The density over (u, v) of each pixel, row by row.
+    densities: Vec<f32>,
+    /// The cumulative distribution of the rows.
+    rows: Vec<f32>,
+    /// The cumulative distribution of the columns, within each row.
+    columns: Vec<Vec<f32>>
+}
+
+/// The index of the interval of the cumulative distribution `cdf`, starting
+/// at 0 and ending at 1, that contains `x`.
+fn sample_cdf(cdf: &[f32], x: f32) -> usize {
+    cdf.partition_point(|&c| c <= x).clamp(1, cdf.len() - 1) - 1
+}
+
+/// The cumulative distribution of `weights`, normalized to end at 1, or
+/// uniform if they are all 0.
+fn cdf(weights: impl Iterator<Item = f32>) -> Vec<f32> {
+    let mut cdf = vec![0.0];
+    for w in weights {
+        cdf.push(cdf.last().unwrap() + w);
+    }
+    let total = *cdf.last().unwrap();
+    let n = cdf.len() - 1;
+    cdf.iter_mut().enumerate().for_each(|(i, c)| *c = if total > 0.0 { *c / total } else { i as f32 / n as f32 });
+    cdf
+}
+
+impl EnvironmentMap {
+    pub fn new(image: ImageTexture, scale: f32) -> Self {
+        let (width, height) = (image.width, image.height);
+        // Rows near the poles cover less solid angle.
+        let weights: Vec<f32> = (0..height).flat_map(|j| {
+            let sin_theta = f32::sin(std::f32::consts::PI * (j as f32 + 0.5) / height as f32);
+            image.pixels[j * width..(j + 1) * width].iter().map(move |c| (0.2126 * c.x() + 0.7152 * c.y() + 0.0722 * c.z()) * sin_theta)
+        }).collect();
+        let total: f32 = weights.iter().sum();
+        let densities = weights.iter().map(|w| if total > 0.0 { w / total * (width * height) as f32 } else { 1.0 }).collect();
+        let rows = cdf((0..height).map(|j| weights[j * width..(j + 1) * width].iter().sum()));
+        let columns = (0..height).map(|j| cdf(weights[j * width..(j + 1) * width].iter().copied())).collect();
+        EnvironmentMap { image, scale, densities, rows, columns }
+    }
+
+    /// The direction at (`u`, `v`), as on a sphere.
+    fn direction(u: f32, v: f32) -> Vec3 {
+        let (sin_theta, cos_theta) = f32::sin_cos(v * std::f32::consts::PI);
+        let (sin_phi, cos_phi) = f32::sin_cos(u * 2.0 * std::f32::consts::PI);
+        Vec3(-cos_phi * sin_theta, -cos_theta, sin_phi * sin_theta)
+    }
+
+    fn value(&self, direction: Vec3) -> Color {
+        let (u, v) = get_sphere_uv(direction.unit_vector());
+        self.image.value(u, v, direction) * self.scale
+    }
+
+    /// The density over (u, v) is constant on each pixel, and dividing it by
+    /// 2π² sin θ converts it to solid angle.
+    fn pdf_value(&self, direction: Vec3) -> f32 {
+        let direction = direction.unit_vector();
+        let (u, v) = get_sphere_uv(direction);
+        // Near the poles, sin θ is more precise from x and z than from v.
+        let sin_theta = f32::sqrt(direction.x() * direction.x() + direction.z() * direction.z());
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        let i = ((u * self.image.width as f32) as usize).min(self.image.width - 1);
+        let j = (((1.0 - v) * self.image.height as f32) as usize).min(self.image.height - 1);
+        self.densities[j * self.image.width + i] / (2.0 * std::f32::consts::PI * std::f32::consts::PI * sin_theta)
+    }
+
+    /// A row, then a column in it, then a point in the pixel.
+    fn random(&self) -> Vec3 {
+        let j = sample_cdf(&self.rows, random::<f32>());
+        let i = sample_cdf(&self.columns[j], random::<f32>());
+        let u = (i as f32 + random::<f32>()) / self.image.width as f32;
+        let v = 1.0 - (j as f32 + random::<f32>()) / self.image.height as f32;
+        EnvironmentMap::direction(u, v)
+    }
+}
+
+impl Background {
+    pub fn value(&self, r: Ray) -> Color {
+        match self {
+            Background::Sky => {
+                let unit_direction: Vec3 = r.direction().unit_vector();
+                let t = 0.5 * (unit_direction.y() + 1.0);
+                // Here Vec3(1.0, 1.0, 1.0) is the color white; and Vec3(0.5, 0.7, 1.0
+                // is the color blue. Both are expressed as RGB values.
+                // We scale 0.0 <= t <= 1 so that when t = 1.0 we get blue. When t = 0.0 we get white.
+                // In between, we get a linear blend.
+                Vec3(1.0, 1.0, 1.0) * (1.0 - t) + Vec3(0.5, 0.7, 1.0) * t
+            }
+            Background::Solid(color) => *color,
+            Background::Environment(map) => map.value(r.direction())
+        }
+    }
+
+    /// Whether the background contributes any light to the scene.
+    pub fn emits(&self) -> bool {
+        match self {
+            Background::Sky => true,
+            Background::Solid(color) => *color != Vec3(0.0, 0.0, 0.0),
+            Background::Environment(_) => true
+        }
+    }
+
+    pub fn pdf_value(&self, direction: Vec3) -> f32 {
+        match self {
+            Background::Environment(map) => map.pdf_value(direction),
+            _ => 1.0 / (4.0 * std::f32::consts::PI)
+        }
+    }
+
+    pub fn random(&self) -> Vec3 {
+        match self {
+            Background::Environment(map) => map.random(),
+            _ => vec3::random_unit_vector()
+        }
+    }
+}
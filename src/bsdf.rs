@@ -0,0 +1,41 @@
+use crate::vec3::{Vec3, Color};
+use crate::pdf::Pdf;
+
+/// How a surface scatters light at a hit point, towards the incoming ray.
+///
+/// As a `Pdf` it samples the directions light is gathered from; `eval`
+/// returns the BSDF times the cosine of `direction` with the normal, the
+/// weight of light arriving from `direction`.
+pub trait Bsdf: Pdf {
+    fn eval(&self, direction: Vec3) -> Color;
+}
+
+/// A BSDF that samples exactly its own distribution, scaled by `attenuation`.
+///
+/// This is the case of `Lambertian` and fuzzy `Metal`, where the value is
+/// `attenuation` times the density. Directions below the surface `normal`
+/// are absorbed.
+pub struct SampledBsdf<P: Pdf> {
+    pub pdf: P,
+    pub attenuation: Color,
+    pub normal: Vec3
+}
+
+impl<P: Pdf> Pdf for SampledBsdf<P> {
+    fn value(&self, direction: Vec3) -> f32 {
+        self.pdf.value(direction)
+    }
+
+    fn generate(&self) -> Vec3 {
+        self.pdf.generate()
+    }
+}
+
+impl<P: Pdf> Bsdf for SampledBsdf<P> {
+    fn eval(&self, direction: Vec3) -> Color {
+        if direction.dot(self.normal) <= 0.0 {
+            return Vec3(0.0, 0.0, 0.0);
+        }
+        self.attenuation * self.pdf.value(direction)
+    }
+}
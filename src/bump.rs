@@ -0,0 +1,124 @@
+use crate::vec3::{Vec3, Color};
+use crate::ray::Ray;
+use crate::hit::{self, HitRecord};
+use crate::material::{Material, Scatter};
+use crate::medium::HomogeneousMedium;
+use crate::texture::Texture;
+
+/// Step of the finite differences taken on height textures.
+const BUMP_DELTA: f32 = 1e-3;
+
+/// A `base` material whose shading normal is read from a tangent-space
+/// normal map: the red, green and blue channels, mapped from [0, 1] to
+/// [-1, 1], are the coordinates of the normal along the tangent, the
+/// bitangent and the surface normal.
+pub struct NormalMap {
+    pub base: Box<dyn Material>,
+    pub normal_map: Box<dyn Texture>
+}
+
+/// A `base` material whose shading normal is tilted by the slopes of a height
+/// texture, scaled by `scale`. Heights are read from the first channel, and
+/// differentiated along u and v, and along the tangent and the bitangent for
+/// solid textures like noise.
+pub struct BumpMap {
+    pub base: Box<dyn Material>,
+    pub height: Box<dyn Texture>,
+    pub scale: f32
+}
+
+/// Bends the shading normal `n` towards the geometric normal `ng` just enough
+/// that the mirror reflection of `wi`, the unit direction towards the viewer,
+/// stays above the surface, with the method of Blender's Cycles. `ng` faces
+/// `wi`. Without it, the reflection off a strongly perturbed normal can go
+/// into the surface, and materials absorb the ray.
+fn ensure_valid_reflection(ng: Vec3, wi: Vec3, n: Vec3) -> Vec3 {
+    let r = n * (2.0 * n.dot(wi)) - wi;
+    // Reflections may always be at least as shallow as the incoming ray.
+    let threshold = f32::min(0.9 * ng.dot(wi), 0.01);
+    if ng.dot(r) >= threshold {
+        return n;
+    }
+
+    // In a frame with `ng` as the z axis and `n` in the x-z plane, find the
+    // normal whose reflection of `wi` is exactly at the threshold.
+    let x = (n - ng * n.dot(ng)).unit_vector();
+    let (ix, iz) = (wi.dot(x), wi.dot(ng));
+    let (ix2, iz2) = (ix * ix, iz * iz);
+    let a = ix2 + iz2;
+    let b = f32::sqrt(ix2 * (a - threshold * threshold).max(0.0));
+    let c = iz * threshold + a;
+
+    let n1_z2 = 0.5 / a * (b + c);
+    let n2_z2 = 0.5 / a * (c - b);
+    let valid = |nz2: f32| nz2 > 1e-5 && nz2 <= 1.0 + 1e-5;
+    let normal = |nz2: f32| (f32::sqrt((1.0 - nz2).max(0.0)), f32::sqrt(nz2.max(0.0)));
+    let reflection_z = |(nx, nz): (f32, f32)| 2.0 * (nx * ix + nz * iz) * nz - iz;
+
+    let (nx, nz) = match (valid(n1_z2), valid(n2_z2)) {
+        (true, true) => {
+            let (n1, n2) = (normal(n1_z2), normal(n2_z2));
+            let (r1, r2) = (reflection_z(n1), reflection_z(n2));
+            // Prefer the valid solution closest to the threshold.
+            if r1 >= 1e-5 && r2 >= 1e-5 {
+                if r1 < r2 { n1 } else { n2 }
+            } else if r1 > r2 {
+                n1
+            } else {
+                n2
+            }
+        }
+        (true, false) => normal(n1_z2),
+        (false, true) => normal(n2_z2),
+        (false, false) => return ng
+    };
+    x * nx + ng * nz
+}
+
+/// `record` with the shading normal `normal`, made valid for reflections, and
+/// its tangent frame rebuilt around it.
+fn shade<'a>(r_in: Ray, record: HitRecord<'a>, normal: Vec3) -> HitRecord<'a> {
+    if !normal.length_squared().is_finite() || normal.length_squared() == 0.0 {
+        return record;
+    }
+    let normal = normal.unit_vector();
+    let normal = ensure_valid_reflection(record.normal, -r_in.direction().unit_vector(), normal);
+    let (tangent, bitangent) = hit::tangent_frame(normal, record.tangent);
+    HitRecord { normal, tangent, bitangent, ..record }
+}
+
+impl Material for NormalMap {
+    fn scatter(&self, r_in: Ray, record: HitRecord) -> Option<Scatter> {
+        let c = self.normal_map.value(record.u, record.v, record.p) * 2.0 - Vec3(1.0, 1.0, 1.0);
+        let normal = record.tangent * c.x() + record.bitangent * c.y() + record.normal * c.z();
+        self.base.scatter(r_in, shade(r_in, record, normal))
+    }
+
+    fn emitted(&self, r_in: Ray, record: HitRecord) -> Color {
+        self.base.emitted(r_in, record)
+    }
+
+    fn medium(&self) -> Option<HomogeneousMedium> {
+        self.base.medium()
+    }
+}
+
+impl Material for BumpMap {
+    fn scatter(&self, r_in: Ray, record: HitRecord) -> Option<Scatter> {
+        let (u, v, p) = (record.u, record.v, record.p);
+        let height = self.height.value(u, v, p).x();
+        let du = (self.height.value(u + BUMP_DELTA, v, p + record.tangent * BUMP_DELTA).x() - height) / BUMP_DELTA;
+        let dv = (self.height.value(u, v + BUMP_DELTA, p + record.bitangent * BUMP_DELTA).x() - height) / BUMP_DELTA;
+
+        let normal = record.normal - (record.tangent * du + record.bitangent * dv) * self.scale;
+        self.base.scatter(r_in, shade(r_in, record, normal))
+    }
+
+    fn emitted(&self, r_in: Ray, record: HitRecord) -> Color {
+        self.base.emitted(r_in, record)
+    }
+
+    fn medium(&self) -> Option<HomogeneousMedium> {
+        self.base.medium()
+    }
+}
@@ -0,0 +1,110 @@
+use crate::vec3::{Vec3, Point3D};
+use crate::ray::Ray;
+use crate::hit::{HitRecord, Hittable};
+use crate::aabb::Aabb;
+
+/// Leaves hold up to this many objects.
+const LEAF_SIZE: usize = 4;
+
+/// A bounding volume hierarchy: `objects` sorted into a tree of nested boxes,
+/// so that a ray only tests the objects whose boxes it goes through. The
+/// boxes are taken over [`time0`, `time1`], and contain moving objects over
+/// that whole time.
+///
+/// Objects need finite bounding boxes: unbounded ones, like planes, should
+/// stay out of the hierarchy.
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    objects: Vec<Box<dyn Hittable>>
+}
+
+/// A node of a `Bvh`, with the objects `start..end` below it. The first
+/// child of an inner node follows it, and `second` is the other one.
+struct BvhNode {
+    bounds: Aabb,
+    start: usize,
+    end: usize,
+    second: Option<usize>
+}
+
+fn centroid(bounds: Aabb) -> Point3D {
+    (bounds.minimum + bounds.maximum) / 2.0
+}
+
+fn axis(v: Vec3, axis: usize) -> f32 {
+    [v.x(), v.y(), v.z()][axis]
+}
+
+impl Bvh {
+    pub fn new(objects: Vec<Box<dyn Hittable>>, time0: f32, time1: f32) -> Self {
+        let mut objects: Vec<(Box<dyn Hittable>, Aabb)> = objects.into_iter()
+            .map(|object| {
+                let bounds = object.bounding_box(time0, time1).expect("objects in a BVH need bounding boxes");
+                (object, bounds)
+            })
+            .collect();
+        let mut nodes = Vec::new();
+        if !objects.is_empty() {
+            let count = objects.len();
+            Bvh::build(&mut nodes, &mut objects, 0, count);
+        }
+        Bvh { nodes, objects: objects.into_iter().map(|(object, _)| object).collect() }
+    }
+
+    /// Adds the node of `objects[start..end]`, splitting them in half along
+    /// the axis where their centers spread the most, and returns its index.
+    fn build(nodes: &mut Vec<BvhNode>, objects: &mut [(Box<dyn Hittable>, Aabb)], start: usize, end: usize) -> usize {
+        let bounds = objects[start..end].iter().map(|(_, b)| *b).reduce(Aabb::surrounding_box).unwrap();
+        let index = nodes.len();
+        nodes.push(BvhNode { bounds, start, end, second: None });
+        if end - start <= LEAF_SIZE {
+            return index;
+        }
+
+        let centers = objects[start..end].iter()
+            .map(|(_, b)| { let c = centroid(*b); Aabb { minimum: c, maximum: c } })
+            .reduce(Aabb::surrounding_box).unwrap();
+        let extent = centers.maximum - centers.minimum;
+        let split_axis = if extent.x() > extent.y() && extent.x() > extent.z() { 0 } else if extent.y() > extent.z() { 1 } else { 2 };
+        objects[start..end].sort_unstable_by(|(_, a), (_, b)| axis(centroid(*a), split_axis).total_cmp(&axis(centroid(*b), split_axis)));
+
+        let middle = (start + end) / 2;
+        Bvh::build(nodes, objects, start, middle);
+        let second = Bvh::build(nodes, objects, middle, end);
+        nodes[index].second = Some(second);
+        index
+    }
+}
+
+impl Hittable for Bvh {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let mut closest: Option<HitRecord> = None;
+        let mut closest_t = t_max;
+        let mut stack = if self.nodes.is_empty() { vec![] } else { vec![0] };
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node.bounds.clip(r, t_min, closest_t).is_none() {
+                continue;
+            }
+            match node.second {
+                Some(second) => {
+                    stack.push(second);
+                    stack.push(index + 1);
+                }
+                None => {
+                    for object in &self.objects[node.start..node.end] {
+                        if let Some(record) = object.hit(r, t_min, closest_t) {
+                            closest_t = record.t;
+                            closest = Some(record);
+                        }
+                    }
+                }
+            }
+        }
+        closest
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        self.nodes.first().map(|node| node.bounds)
+    }
+}
@@ -15,6 +15,7 @@ pub struct Camera {
 }
 
 impl Camera {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(lookfrom: Vec3, 
         lookat: Vec3, 
         vup: Vec3, 
@@ -62,6 +63,15 @@ impl Camera {
         let offset = self.u * rd.x() + self.v * rd.y();
         Ray(self.origin + offset,
             self.lower_left_corner + self.horizontal*s + self.vertical*t - self.origin - offset, 
-            vec3::random_double(Some(self.time_0), Some(self.time_1)))
+            vec3::random_double(Some(self.time_0), Some(self.time_1)),
+            0.0)
+    }
+
+    /// The size of a pixel of an image `image_width` pixels wide, at the
+    /// depth of `p` in front of the camera.
+    pub fn pixel_size(&self, p: Vec3, image_width: usize) -> f32 {
+        let center = self.lower_left_corner + self.horizontal / 2.0 + self.vertical / 2.0 - self.origin;
+        let depth = (p - self.origin).dot(center) / center.length_squared();
+        depth.max(0.0) * self.horizontal.length() / image_width as f32
     }
 }
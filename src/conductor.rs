@@ -0,0 +1,46 @@
+use crate::vec3::{Vec3, Color};
+use crate::ray::Ray;
+use crate::hit::HitRecord;
+use crate::material::{Material, Scatter};
+use crate::texture::Texture;
+use crate::microfacet::{Ggx, Fresnel, scatter_conductor};
+
+/// Measured complex indices of refraction of common metals, relative to air:
+/// name, real part `eta` and imaginary part `k`.
+///
+/// The spectral measurements are integrated against the red, green and blue
+/// primaries of sRGB.
+pub const CONDUCTOR_PRESETS: [(&str, Color, Color); 5] = [
+    ("gold", Vec3(0.143119, 0.374957, 1.44248), Vec3(3.98316, 2.38572, 1.60322)),
+    ("silver", Vec3(0.155265, 0.116723, 0.138342), Vec3(4.82835, 3.12225, 2.14696)),
+    ("copper", Vec3(0.200438, 0.924033, 1.10221), Vec3(3.91295, 2.45285, 2.14219)),
+    ("aluminium", Vec3(1.65746, 0.880369, 0.521229), Vec3(9.22387, 6.26952, 4.837)),
+    ("chromium", Vec3(4.36968, 2.9167, 1.6547), Vec3(5.20643, 4.23136, 3.75495)),
+];
+
+/// A metal described by its complex index of refraction `eta` + i`k`, with
+/// the exact conductor Fresnel equations. Its color comes from the index of
+/// refraction, rather than from an albedo.
+///
+/// `roughness` is read from the first channel of its texture.
+pub struct Conductor {
+    pub eta: Color,
+    pub k: Color,
+    pub roughness: Box<dyn Texture>
+}
+
+impl Conductor {
+    /// A metal from `CONDUCTOR_PRESETS`, by name. `None` if there is no such preset.
+    pub fn preset(name: &str, roughness: Box<dyn Texture>) -> Option<Self> {
+        CONDUCTOR_PRESETS.iter()
+            .find(|(preset, _, _)| *preset == name)
+            .map(|(_, eta, k)| Conductor { eta: *eta, k: *k, roughness })
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(&self, r_in: Ray, record: HitRecord) -> Option<Scatter> {
+        let distribution = Ggx::from_roughness(self.roughness.value(record.u, record.v, record.p).x());
+        scatter_conductor(r_in, record, distribution, Fresnel::Conductor { eta: self.eta, k: self.k })
+    }
+}
@@ -0,0 +1,118 @@
+use crate::ray::Ray;
+use crate::hit::{HitRecord, Hittable, Span};
+use crate::aabb::Aabb;
+
+/// Everything inside `a` or `b`.
+pub struct Union {
+    pub a: Box<dyn Hittable>,
+    pub b: Box<dyn Hittable>
+}
+
+/// Everything inside both `a` and `b`, like a lens carved out of two spheres.
+pub struct Intersection {
+    pub a: Box<dyn Hittable>,
+    pub b: Box<dyn Hittable>
+}
+
+/// Everything inside `a` but not inside `b`, like a box with holes drilled by
+/// cylinders. The walls left by `b` have its material, and face into it.
+pub struct Difference {
+    pub a: Box<dyn Hittable>,
+    pub b: Box<dyn Hittable>
+}
+
+/// The spans where `inside` holds, given whether a point is inside the spans
+/// `a` and inside the spans `b`. Each boundary keeps the record of the surface
+/// it comes from, turned to face the outside of the result.
+fn combine<'a, F>(a: Vec<Span<'a>>, b: Vec<Span<'a>>, inside: F) -> Vec<Span<'a>>
+where
+    F: Fn(bool, bool) -> bool
+{
+    // Every boundary of the operands: its record, whether it is on `a`, and
+    // whether the ray enters the operand there.
+    let mut boundaries: Vec<(HitRecord, bool, bool)> = Vec::with_capacity(2 * (a.len() + b.len()));
+    for (spans, on_a) in [(a, true), (b, false)] {
+        for span in spans {
+            boundaries.push((span.enter, on_a, true));
+            boundaries.push((span.exit, on_a, false));
+        }
+    }
+    boundaries.sort_by(|x, y| x.0.t.total_cmp(&y.0.t));
+
+    let (mut in_a, mut in_b) = (false, false);
+    let mut enter = None;
+    let mut spans = Vec::new();
+    for (record, on_a, entering) in boundaries {
+        let was_inside = inside(in_a, in_b);
+        if on_a {
+            in_a = entering;
+        } else {
+            in_b = entering;
+        }
+        // The normal of a record always faces the ray, so turning a surface
+        // inside out only changes which side the ray is on.
+        match (was_inside, inside(in_a, in_b)) {
+            (false, true) => enter = Some(HitRecord { front_face: true, ..record }),
+            (true, false) => {
+                if let Some(enter) = enter.take() {
+                    spans.push(Span { enter, exit: HitRecord { front_face: false, ..record } });
+                }
+            }
+            _ => {}
+        }
+    }
+    spans
+}
+
+/// The first boundary of `spans` within [`t_min`, `t_max`].
+fn first_hit(spans: Vec<Span>, t_min: f32, t_max: f32) -> Option<HitRecord> {
+    spans.into_iter()
+        .flat_map(|span| [span.enter, span.exit])
+        .find(|record| record.t >= t_min && record.t <= t_max)
+}
+
+impl Hittable for Union {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        self.bounding_box(r.time(), r.time())?.clip(r, t_min, t_max)?;
+        first_hit(self.spans(r), t_min, t_max)
+    }
+
+    fn spans(&self, r: Ray) -> Vec<Span<'_>> {
+        combine(self.a.spans(r), self.b.spans(r), |in_a, in_b| in_a || in_b)
+    }
+
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb> {
+        Some(Aabb::surrounding_box(self.a.bounding_box(time0, time1)?, self.b.bounding_box(time0, time1)?))
+    }
+}
+
+impl Hittable for Intersection {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        self.bounding_box(r.time(), r.time())?.clip(r, t_min, t_max)?;
+        first_hit(self.spans(r), t_min, t_max)
+    }
+
+    fn spans(&self, r: Ray) -> Vec<Span<'_>> {
+        combine(self.a.spans(r), self.b.spans(r), |in_a, in_b| in_a && in_b)
+    }
+
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb> {
+        Some(Aabb::overlap(self.a.bounding_box(time0, time1)?, self.b.bounding_box(time0, time1)?))
+    }
+}
+
+impl Hittable for Difference {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        self.bounding_box(r.time(), r.time())?.clip(r, t_min, t_max)?;
+        first_hit(self.spans(r), t_min, t_max)
+    }
+
+    fn spans(&self, r: Ray) -> Vec<Span<'_>> {
+        combine(self.a.spans(r), self.b.spans(r), |in_a, in_b| in_a && !in_b)
+    }
+
+    /// What is left of `a` is within its box.
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb> {
+        self.a.bounding_box(time0, time1)
+    }
+}
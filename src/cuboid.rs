@@ -0,0 +1,73 @@
+use crate::vec3::{Vec3, Point3D};
+use crate::ray::Ray;
+use crate::hit::{HitRecord, Hittable, Span};
+use crate::aabb::Aabb;
+use crate::material::Material;
+
+const AXES: [Vec3; 3] = [Vec3(1.0, 0.0, 0.0), Vec3(0.0, 1.0, 0.0), Vec3(0.0, 0.0, 1.0)];
+
+/// A solid axis-aligned box between the corners `minimum` and `maximum`. Each
+/// face is mapped to [0, 1] x [0, 1] by the two axes it spans, in order.
+pub struct Cuboid {
+    pub minimum: Point3D,
+    pub maximum: Point3D,
+    pub material: Box<dyn Material>
+}
+
+fn coordinates(v: Vec3) -> [f32; 3] {
+    [v.x(), v.y(), v.z()]
+}
+
+impl Cuboid {
+    /// The record of the hit at `t` along `r`, on the face across `axis` with
+    /// `outward_normal`.
+    fn record(&self, r: Ray, t: f32, axis: usize, outward_normal: Vec3) -> HitRecord<'_> {
+        let (u_axis, v_axis) = ((axis + 1) % 3, (axis + 2) % 3);
+        let p = coordinates(r.at(t));
+        let (minimum, maximum) = (coordinates(self.minimum), coordinates(self.maximum));
+        let u = (p[u_axis] - minimum[u_axis]) / (maximum[u_axis] - minimum[u_axis]);
+        let v = (p[v_axis] - minimum[v_axis]) / (maximum[v_axis] - minimum[v_axis]);
+
+        HitRecord::new(r, t, outward_normal, u, v, AXES[u_axis], &*self.material)
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let span = self.spans(r).pop()?;
+        [span.enter, span.exit].into_iter().find(|record| record.t >= t_min && record.t <= t_max)
+    }
+
+    /// The slab method, keeping track of the faces the ray crosses.
+    fn spans(&self, r: Ray) -> Vec<Span<'_>> {
+        let (origin, direction) = (coordinates(r.origin()), coordinates(r.direction()));
+        let (minimum, maximum) = (coordinates(self.minimum), coordinates(self.maximum));
+        let mut enter = (f32::NEG_INFINITY, 0, Vec3(0.0, 0.0, 0.0));
+        let mut exit = (f32::INFINITY, 0, Vec3(0.0, 0.0, 0.0));
+        for axis in 0..3 {
+            let inv_d = 1.0 / direction[axis];
+            let t0 = (minimum[axis] - origin[axis]) * inv_d;
+            let t1 = (maximum[axis] - origin[axis]) * inv_d;
+            // The ray enters through the face it is heading away from.
+            let (t_near, t_far, normal) = if inv_d < 0.0 { (t1, t0, AXES[axis]) } else { (t0, t1, -AXES[axis]) };
+            if t_near > enter.0 {
+                enter = (t_near, axis, normal);
+            }
+            if t_far < exit.0 {
+                exit = (t_far, axis, -normal);
+            }
+        }
+        if enter.0 >= exit.0 || enter.0 == f32::NEG_INFINITY || exit.0 == f32::INFINITY {
+            return vec![];
+        }
+
+        vec![Span {
+            enter: self.record(r, enter.0, enter.1, enter.2),
+            exit: self.record(r, exit.0, exit.1, exit.2)
+        }]
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        Some(Aabb { minimum: self.minimum, maximum: self.maximum })
+    }
+}
@@ -0,0 +1,191 @@
+use crate::vec3::{Vec3, Point3D};
+use crate::ray::Ray;
+use crate::hit::{HitRecord, Hittable};
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::onb::Onb;
+
+/// How the surface of a `Curve` is shaped across its width.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CurveType {
+    /// A flat ribbon, always facing the ray, like a blade of grass.
+    Flat,
+    /// A ribbon facing the ray, whose normal turns across it as if it were a
+    /// round tube, like a hair. It is much cheaper than a true tube.
+    Cylinder
+}
+
+/// A cubic Bézier curve through `control_points`, with a width going from
+/// `widths[0]` at its start to `widths[1]` at its end. u goes along the
+/// curve, and v across it. The tangent of hits follows the curve.
+///
+/// Hits are found as in pbrt: in a frame where the ray is the z axis, the
+/// curve is split in half recursively, skipping halves whose bounds miss the
+/// ray, until the pieces are nearly straight and can be tested as segments.
+pub struct Curve {
+    pub control_points: [Point3D; 4],
+    pub widths: [f32; 2],
+    pub kind: CurveType,
+    pub material: Box<dyn Material>
+}
+
+/// A hit found by the recursive intersection, in the frame of the ray.
+struct CurveHit {
+    z: f32,
+    u: f32,
+    v: f32
+}
+
+fn lerp(t: f32, a: Vec3, b: Vec3) -> Vec3 {
+    a * (1.0 - t) + b * t
+}
+
+/// The point at `u` on the Bézier curve with control points `cp`, and the
+/// derivative of the curve there, with de Casteljau's algorithm.
+fn eval_bezier(cp: &[Vec3; 4], u: f32) -> (Point3D, Vec3) {
+    let cp1 = [lerp(u, cp[0], cp[1]), lerp(u, cp[1], cp[2]), lerp(u, cp[2], cp[3])];
+    let cp2 = [lerp(u, cp1[0], cp1[1]), lerp(u, cp1[1], cp1[2])];
+    let derivative = if (cp2[1] - cp2[0]).length_squared() > 0.0 {
+        (cp2[1] - cp2[0]) * 3.0
+    } else {
+        // Control points can coincide at the ends.
+        cp[3] - cp[0]
+    };
+    (lerp(u, cp2[0], cp2[1]), derivative)
+}
+
+/// The control points of both halves of a Bézier curve. The middle one is
+/// shared.
+fn subdivide(cp: &[Vec3; 4]) -> [Vec3; 7] {
+    [
+        cp[0],
+        (cp[0] + cp[1]) / 2.0,
+        (cp[0] + cp[1] * 2.0 + cp[2]) / 4.0,
+        (cp[0] + cp[1] * 3.0 + cp[2] * 3.0 + cp[3]) / 8.0,
+        (cp[1] + cp[2] * 2.0 + cp[3]) / 4.0,
+        (cp[2] + cp[3]) / 2.0,
+        cp[3]
+    ]
+}
+
+impl Curve {
+    fn width(&self, u: f32) -> f32 {
+        self.widths[0] * (1.0 - u) + self.widths[1] * u
+    }
+
+    /// The closest hit with the part of the curve between `u0` and `u1`, with
+    /// control points `cp` in the frame of the ray, at a depth within
+    /// [`z_min`, `z_max`]. `z_max` shrinks to the closest hit found so far.
+    fn recursive_hit(&self, cp: &[Vec3; 4], (u0, u1): (f32, f32), depth: u32, z_min: f32, z_max: &mut f32) -> Option<CurveHit> {
+        if depth > 0 {
+            let split = subdivide(cp);
+            let u_middle = (u0 + u1) / 2.0;
+            let mut closest = None;
+            for (half, range) in [([split[0], split[1], split[2], split[3]], (u0, u_middle)), ([split[3], split[4], split[5], split[6]], (u_middle, u1))] {
+                // Bounds of the half, widened by its width, against the ray.
+                let margin = 0.5 * self.width(range.0).max(self.width(range.1));
+                let (low, high) = half.iter().fold((Vec3(f32::INFINITY, f32::INFINITY, f32::INFINITY), Vec3(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY)), |(low, high), p| {
+                    (Vec3(low.x().min(p.x()), low.y().min(p.y()), low.z().min(p.z())), Vec3(high.x().max(p.x()), high.y().max(p.y()), high.z().max(p.z())))
+                });
+                if low.x() - margin > 0.0 || high.x() + margin < 0.0 || low.y() - margin > 0.0 || high.y() + margin < 0.0 {
+                    continue;
+                }
+                if low.z() - margin > *z_max || high.z() + margin < z_min {
+                    continue;
+                }
+                if let Some(hit) = self.recursive_hit(&half, range, depth - 1, z_min, z_max) {
+                    closest = Some(hit);
+                }
+            }
+            return closest;
+        }
+
+        // The ray must be past the perpendiculars to the curve at both ends,
+        // so that neighbouring pieces do not both report it.
+        let edge = (cp[1].y() - cp[0].y()) * -cp[0].y() + cp[0].x() * (cp[0].x() - cp[1].x());
+        if edge < 0.0 {
+            return None;
+        }
+        let edge = (cp[2].y() - cp[3].y()) * -cp[3].y() + cp[3].x() * (cp[3].x() - cp[2].x());
+        if edge < 0.0 {
+            return None;
+        }
+
+        // The closest point to the ray on the segment between the ends.
+        let (dx, dy) = (cp[3].x() - cp[0].x(), cp[3].y() - cp[0].y());
+        let denominator = dx * dx + dy * dy;
+        if denominator == 0.0 {
+            return None;
+        }
+        let w = (-cp[0].x() * dx - cp[0].y() * dy) / denominator;
+        let u = (u0 + (u1 - u0) * w).clamp(u0, u1);
+        let hit_width = self.width(u);
+        let (pc, dpcdw) = eval_bezier(cp, w.clamp(0.0, 1.0));
+        let distance2 = pc.x() * pc.x() + pc.y() * pc.y();
+        if distance2 > hit_width * hit_width * 0.25 || pc.z() < z_min || pc.z() > *z_max {
+            return None;
+        }
+
+        // v is 0.5 on the curve, and goes to 0 and 1 on its edges, on the
+        // right and left of the ray.
+        let offset = distance2.sqrt() / hit_width;
+        let left = dpcdw.x() * -pc.y() + pc.x() * dpcdw.y() > 0.0;
+        let v = if left { 0.5 + offset } else { 0.5 - offset };
+        *z_max = pc.z();
+        Some(CurveHit { z: pc.z(), u, v })
+    }
+}
+
+impl Hittable for Curve {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        self.bounding_box(r.time(), r.time())?.clip(r, t_min, t_max)?;
+
+        let frame = Onb::build_from_w(r.direction());
+        let cp = self.control_points.map(|p| frame.to_local(p - r.origin()));
+        let length = r.direction().length();
+
+        // Deep enough that the pieces are within a twentieth of the width of
+        // straight segments.
+        let curvature = (0..2)
+            .map(|i| {
+                let d = cp[i] - cp[i + 1] * 2.0 + cp[i + 2];
+                d.x().abs().max(d.y().abs()).max(d.z().abs())
+            })
+            .fold(0.0, f32::max);
+        let epsilon = 0.05 * self.widths[0].max(self.widths[1]);
+        let depth = if curvature > 0.0 {
+            (f32::log2(std::f32::consts::SQRT_2 * 6.0 * curvature / (8.0 * epsilon)) / 2.0).round().clamp(0.0, 10.0) as u32
+        } else {
+            0
+        };
+
+        let mut z_max = t_max * length;
+        let hit = self.recursive_hit(&cp, (0.0, 1.0), depth, t_min * length, &mut z_max)?;
+
+        // The ribbon is spanned by the tangent and a side direction across
+        // the ray, and faces the ray. Cylinders turn the normal towards the
+        // side across the width, by up to a right angle on the edges.
+        let (_, dpdu) = eval_bezier(&self.control_points, hit.u);
+        let tangent = frame.to_local(dpdu).unit_vector();
+        let side = Vec3(-tangent.y(), tangent.x(), 0.0).unit_vector();
+        let facing = side.cross(tangent);
+        let normal = match self.kind {
+            CurveType::Flat => facing,
+            CurveType::Cylinder => {
+                let (sin, cos) = f32::sin_cos((hit.v - 0.5) * std::f32::consts::PI);
+                facing * cos + side * sin
+            }
+        };
+
+        Some(HitRecord::new(r, hit.z / length, frame.local(normal), hit.u, hit.v, dpdu, &*self.material))
+    }
+
+    /// Curves are within the hull of their control points.
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        let margin = 0.5 * self.widths[0].max(self.widths[1]);
+        let margin = Vec3(margin, margin, margin);
+        let cp = self.control_points;
+        let hull = cp[1..].iter().fold(Aabb::around(cp[0], margin), |hull, &p| Aabb::surrounding_box(hull, Aabb::around(p, margin)));
+        Some(hull)
+    }
+}
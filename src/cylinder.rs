@@ -0,0 +1,147 @@
+use crate::vec3::{Vec3, Point3D};
+use crate::ray::Ray;
+use crate::hit::{HitRecord, Hittable};
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::onb::Onb;
+use crate::polynomial;
+
+/// A cylinder of `radius` standing on the disk around `center`, and rising
+/// `height` along the unit `axis`. It is closed by disks at both ends when
+/// `capped`, and open like a tube otherwise.
+pub struct Cylinder {
+    pub center: Point3D,
+    pub axis: Vec3,
+    pub height: f32,
+    pub radius: f32,
+    pub capped: bool,
+    pub material: Box<dyn Material>
+}
+
+/// A cone standing on the disk of `radius` around `center`, with its apex
+/// `height` along the unit `axis`. It is closed by its base when `capped`.
+pub struct Cone {
+    pub center: Point3D,
+    pub axis: Vec3,
+    pub height: f32,
+    pub radius: f32,
+    pub capped: bool,
+    pub material: Box<dyn Material>
+}
+
+/// A hit in the frame of a cylinder or a cone, where the axis is z and the
+/// base is at the origin.
+struct LocalHit {
+    t: f32,
+    p: Vec3,
+    outward_normal: Vec3,
+    v: f32
+}
+
+impl LocalHit {
+    /// The record of this hit, with the frame turned back into world space.
+    /// u turns around the axis.
+    fn record<'a>(self, r: Ray, frame: Onb, material: &'a dyn Material) -> HitRecord<'a> {
+        let phi = f32::atan2(self.p.y(), self.p.x());
+        let u = (phi + std::f32::consts::PI) / (2.0 * std::f32::consts::PI);
+        let dpdu = frame.local(Vec3(-self.p.y(), self.p.x(), 0.0));
+        HitRecord::new(r, self.t, frame.local(self.outward_normal), u, self.v, dpdu, material)
+    }
+}
+
+/// The closest hit of the local ray (`origin`, `direction`) with the disk of
+/// `radius` in the plane z = `z`, if closer than `closest`.
+fn cap_hit(origin: Vec3, direction: Vec3, z: f32, radius: f32, outward_normal: Vec3, t_min: f32, closest: f32) -> Option<LocalHit> {
+    let t = (z - origin.z()) / direction.z();
+    if !(t > t_min && t < closest) {
+        return None;
+    }
+    let p = origin + direction * t;
+    let distance = f32::sqrt(p.x() * p.x() + p.y() * p.y());
+    if distance > radius {
+        return None;
+    }
+    Some(LocalHit { t, p, outward_normal, v: distance / radius })
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let frame = Onb::build_from_w(self.axis);
+        let o = frame.to_local(r.origin() - self.center);
+        let d = frame.to_local(r.direction());
+        let mut closest: Option<LocalHit> = None;
+
+        // The side: x² + y² = radius².
+        let a = (d.x() * d.x() + d.y() * d.y()) as f64;
+        let b = 2.0 * (o.x() * d.x() + o.y() * d.y()) as f64;
+        let c = (o.x() * o.x() + o.y() * o.y() - self.radius * self.radius) as f64;
+        for t in polynomial::solve_quadratic(a, b, c) {
+            let t = t as f32;
+            let p = o + d * t;
+            if t > t_min && t < t_max && p.z() >= 0.0 && p.z() <= self.height {
+                let outward_normal = Vec3(p.x(), p.y(), 0.0) / self.radius;
+                closest = Some(LocalHit { t, p, outward_normal, v: p.z() / self.height });
+                break;
+            }
+        }
+
+        if self.capped {
+            for (z, normal) in [(0.0, Vec3(0.0, 0.0, -1.0)), (self.height, Vec3(0.0, 0.0, 1.0))] {
+                let t_max = closest.as_ref().map_or(t_max, |hit| hit.t);
+                if let Some(hit) = cap_hit(o, d, z, self.radius, normal, t_min, t_max) {
+                    closest = Some(hit);
+                }
+            }
+        }
+
+        closest.map(|hit| hit.record(r, frame, &*self.material))
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        let axis = self.axis.unit_vector();
+        let top = self.center + axis * self.height;
+        Some(Aabb::surrounding_box(Aabb::disk(self.center, axis, self.radius), Aabb::disk(top, axis, self.radius)))
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let frame = Onb::build_from_w(self.axis);
+        let o = frame.to_local(r.origin() - self.center);
+        let d = frame.to_local(r.direction());
+        let mut closest: Option<LocalHit> = None;
+
+        // The side: x² + y² = (k (height - z))², with k the slope of the cone.
+        let k = self.radius / self.height;
+        let k2 = k * k;
+        let w = self.height - o.z();
+        let a = (d.x() * d.x() + d.y() * d.y() - k2 * d.z() * d.z()) as f64;
+        let b = 2.0 * (o.x() * d.x() + o.y() * d.y() + k2 * w * d.z()) as f64;
+        let c = (o.x() * o.x() + o.y() * o.y() - k2 * w * w) as f64;
+        for t in polynomial::solve_quadratic(a, b, c) {
+            let t = t as f32;
+            let p = o + d * t;
+            // The equation also holds on the mirrored cone above the apex.
+            if t > t_min && t < t_max && p.z() >= 0.0 && p.z() <= self.height {
+                let outward_normal = Vec3(p.x(), p.y(), k2 * (self.height - p.z())).unit_vector();
+                closest = Some(LocalHit { t, p, outward_normal, v: p.z() / self.height });
+                break;
+            }
+        }
+
+        if self.capped {
+            let t_max = closest.as_ref().map_or(t_max, |hit| hit.t);
+            if let Some(hit) = cap_hit(o, d, 0.0, self.radius, Vec3(0.0, 0.0, -1.0), t_min, t_max) {
+                closest = Some(hit);
+            }
+        }
+
+        closest.map(|hit| hit.record(r, frame, &*self.material))
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        let axis = self.axis.unit_vector();
+        let apex = Aabb::around(self.center + axis * self.height, Vec3(0.0, 0.0, 0.0));
+        Some(Aabb::surrounding_box(Aabb::disk(self.center, axis, self.radius), apex))
+    }
+}
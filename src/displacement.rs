@@ -0,0 +1,48 @@
+use crate::vec3::Vec3;
+use crate::texture::Texture;
+use crate::mesh::PolygonMesh;
+use crate::sphere::get_sphere_uv;
+
+/// True displacement of meshes: vertices move along their normals by the
+/// first channel of the `height` texture, times `scale`. Unlike bump mapping,
+/// the shape changes, along with its silhouette and shadows.
+///
+/// Meshes have no texture coordinates, so heights are looked up at the
+/// position of the vertex, for solid textures like noise, and at (u, v) from
+/// the direction of its normal, as on a sphere.
+///
+/// Meshes need enough vertices to follow the texture: they are usually
+/// tessellated or subdivided first. Triangle meshes, their bounding boxes and
+/// their BVH are then built from the displaced vertices, so that they contain
+/// the whole displaced surface.
+pub struct Displacement {
+    pub height: Box<dyn Texture>,
+    pub scale: f32
+}
+
+impl Displacement {
+    /// `mesh` with its vertices displaced. The normal of a vertex averages
+    /// the faces around it, weighted by their area, even across creases, so
+    /// that the surface does not tear. Creases are kept, and normals are
+    /// computed again by the triangle mesh built from the result.
+    pub fn apply(&self, mesh: &PolygonMesh) -> PolygonMesh {
+        let mut normals = vec![Vec3(0.0, 0.0, 0.0); mesh.positions.len()];
+        for (f, face) in mesh.faces.iter().enumerate() {
+            let normal = mesh.face_normal(f);
+            for &v in face {
+                normals[v] = normals[v] + normal;
+            }
+        }
+
+        let positions = mesh.positions.iter().zip(normals).map(|(&p, normal)| {
+            if normal.length_squared() == 0.0 {
+                // Not part of any face.
+                return p;
+            }
+            let normal = normal.unit_vector();
+            let (u, v) = get_sphere_uv(normal);
+            p + normal * self.height.value(u, v, p).x() * self.scale
+        }).collect();
+        PolygonMesh { positions, ..mesh.clone() }
+    }
+}
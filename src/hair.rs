@@ -0,0 +1,307 @@
+use std::f32::consts::PI;
+use crate::vec3::{Vec3, Color};
+use crate::ray::Ray;
+use crate::hit::HitRecord;
+use crate::material::{Material, Scatter};
+use crate::bsdf::Bsdf;
+use crate::pdf::Pdf;
+use crate::onb::Onb;
+use crate::microfacet::fresnel_dielectric;
+use rand::random;
+
+/// Scattering orders modelled separately: reflection (R), transmission
+/// through the fiber (TT) and one internal reflection (TRT). Longer paths are
+/// lumped together.
+const P_MAX: usize = 3;
+
+/// Absorption coefficients of eumelanin and pheomelanin, the pigments of
+/// dark and red hair, per unit of concentration.
+const EUMELANIN_SIGMA_A: Color = Vec3(0.419, 0.697, 1.37);
+const PHEOMELANIN_SIGMA_A: Color = Vec3(0.187, 0.4, 1.05);
+
+/// Hair fibers, with the model of d'Eon et al. and Chiang et al., as in pbrt:
+/// light reflects off the fiber or goes through it, getting absorbed by
+/// `sigma_a` per unit of fiber diameter, and leaves with a longitudinal
+/// roughness `beta_m` and an azimuthal roughness `beta_n`, both in [0, 1].
+/// The scales on the surface tilt the reflections by `alpha` degrees.
+///
+/// Meant for `Curve`s: the tangent of hits has to follow the fiber, and v to
+/// go across it.
+pub struct Hair {
+    pub sigma_a: Color,
+    pub beta_m: f32,
+    pub beta_n: f32,
+    pub alpha: f32,
+    pub index_of_refraction: f32
+}
+
+impl Hair {
+    /// Hair colored by the concentrations of `eumelanin`, from 0 for blond to
+    /// 8 for black, and `pheomelanin`, for red hair.
+    pub fn from_melanin(eumelanin: f32, pheomelanin: f32) -> Self {
+        Hair::from_sigma_a(EUMELANIN_SIGMA_A * eumelanin + PHEOMELANIN_SIGMA_A * pheomelanin)
+    }
+
+    /// Hair whose multiple scattering has about the color `color`, with the
+    /// fit of Chiang et al.
+    pub fn from_color(color: Color, beta_n: f32) -> Self {
+        let denominator = 5.969 - 0.215 * beta_n + 2.532 * beta_n.powi(2) - 10.73 * beta_n.powi(3) + 5.574 * beta_n.powi(4) + 0.245 * beta_n.powi(5);
+        let sigma_a = |c: f32| (c.max(1e-4).ln() / denominator).powi(2);
+        Hair { beta_n, ..Hair::from_sigma_a(Vec3(sigma_a(color.x()), sigma_a(color.y()), sigma_a(color.z()))) }
+    }
+
+    fn from_sigma_a(sigma_a: Color) -> Self {
+        Hair { sigma_a, beta_m: 0.3, beta_n: 0.3, alpha: 2.0, index_of_refraction: 1.55 }
+    }
+}
+
+/// The BSDF of `Hair` at a hit at offset `h` across the fiber, in [-1, 1],
+/// towards `wo`. Directions are in a frame where x is along the fiber and
+/// z is the normal.
+struct HairBsdf {
+    frame: Onb,
+    gamma_o: f32,
+    gamma_t: f32,
+    sin_theta_o: f32,
+    cos_theta_o: f32,
+    phi_o: f32,
+    /// Longitudinal variance of each scattering order.
+    v: [f32; P_MAX + 1],
+    /// Azimuthal logistic scale.
+    s: f32,
+    /// sin and cos of 2^k alpha, for the tilts of each order.
+    sin_2k_alpha: [f32; 3],
+    cos_2k_alpha: [f32; 3],
+    /// Attenuation of each order, and the chance of sampling it.
+    ap: [Color; P_MAX + 1],
+    ap_pdf: [f32; P_MAX + 1]
+}
+
+fn safe_sqrt(x: f32) -> f32 {
+    x.max(0.0).sqrt()
+}
+
+fn safe_asin(x: f32) -> f32 {
+    x.clamp(-1.0, 1.0).asin()
+}
+
+/// The modified Bessel function of the first kind, of order 0.
+fn i0(x: f32) -> f32 {
+    let mut value = 0.0;
+    let mut x2i = 1.0;
+    let mut factorial: f32 = 1.0;
+    let mut four_i = 1.0;
+    for i in 0..10 {
+        if i > 1 {
+            factorial *= i as f32;
+        }
+        value += x2i / (four_i * factorial * factorial);
+        x2i *= x * x;
+        four_i *= 4.0;
+    }
+    value
+}
+
+fn log_i0(x: f32) -> f32 {
+    if x > 12.0 {
+        x + 0.5 * (-f32::ln(2.0 * PI) + f32::ln(1.0 / x) + 1.0 / (8.0 * x))
+    } else {
+        i0(x).ln()
+    }
+}
+
+/// The longitudinal scattering function, with variance `v`.
+fn mp(cos_theta_i: f32, cos_theta_o: f32, sin_theta_i: f32, sin_theta_o: f32, v: f32) -> f32 {
+    let a = cos_theta_i * cos_theta_o / v;
+    let b = sin_theta_i * sin_theta_o / v;
+    if v <= 0.1 {
+        // In logarithms, to avoid overflows of sinh and I0.
+        f32::exp(log_i0(a) - b - 1.0 / v + std::f32::consts::LN_2 + f32::ln(1.0 / (2.0 * v)))
+    } else {
+        f32::exp(-b) * i0(a) / (f32::sinh(1.0 / v) * 2.0 * v)
+    }
+}
+
+/// The attenuation of each scattering order, from the Fresnel reflectance
+/// and the transmittance `t` of one crossing of the fiber.
+fn ap(cos_theta_o: f32, eta: f32, h: f32, t: Color) -> [Color; P_MAX + 1] {
+    let cos_gamma_o = safe_sqrt(1.0 - h * h);
+    let f = fresnel_dielectric(cos_theta_o * cos_gamma_o, eta);
+    let r = Vec3(f, f, f);
+    let tt = t * ((1.0 - f) * (1.0 - f));
+    let trt = tt * t * f;
+    let rest = |c: f32, t: f32| c * f * t / (1.0 - t * f);
+    let longer = Vec3(rest(trt.x(), t.x()), rest(trt.y(), t.y()), rest(trt.z(), t.z()));
+    [r, tt, trt, longer]
+}
+
+/// The azimuth of the direction leaving after scattering order `p`.
+fn phi(p: usize, gamma_o: f32, gamma_t: f32) -> f32 {
+    let p = p as f32;
+    2.0 * p * gamma_t - 2.0 * gamma_o + p * PI
+}
+
+fn logistic(x: f32, s: f32) -> f32 {
+    let x = x.abs();
+    f32::exp(-x / s) / (s * (1.0 + f32::exp(-x / s)).powi(2))
+}
+
+fn logistic_cdf(x: f32, s: f32) -> f32 {
+    1.0 / (1.0 + f32::exp(-x / s))
+}
+
+fn trimmed_logistic(x: f32, s: f32, a: f32, b: f32) -> f32 {
+    logistic(x, s) / (logistic_cdf(b, s) - logistic_cdf(a, s))
+}
+
+fn sample_trimmed_logistic(u: f32, s: f32, a: f32, b: f32) -> f32 {
+    let k = logistic_cdf(b, s) - logistic_cdf(a, s);
+    let x = -s * f32::ln(1.0 / (u * k + logistic_cdf(a, s)) - 1.0);
+    x.clamp(a, b)
+}
+
+/// The azimuthal scattering function of order `p`, for a change of azimuth
+/// `phi_difference`.
+fn np(phi_difference: f32, p: usize, s: f32, gamma_o: f32, gamma_t: f32) -> f32 {
+    let mut dphi = phi_difference - phi(p, gamma_o, gamma_t);
+    while dphi > PI {
+        dphi -= 2.0 * PI;
+    }
+    while dphi < -PI {
+        dphi += 2.0 * PI;
+    }
+    trimmed_logistic(dphi, s, -PI, PI)
+}
+
+impl HairBsdf {
+    fn new(hair: &Hair, h: f32, frame: Onb, wo: Vec3) -> Self {
+        let wo = frame.to_local(wo);
+        let eta = hair.index_of_refraction;
+        let sin_theta_o = wo.x();
+        let cos_theta_o = safe_sqrt(1.0 - sin_theta_o * sin_theta_o);
+        let phi_o = f32::atan2(wo.z(), wo.y());
+
+        // The refracted ray inside the fiber, and its transmittance.
+        let sin_theta_t = sin_theta_o / eta;
+        let cos_theta_t = safe_sqrt(1.0 - sin_theta_t * sin_theta_t);
+        let etap = f32::sqrt(eta * eta - sin_theta_o * sin_theta_o) / cos_theta_o;
+        let sin_gamma_t = h / etap;
+        let cos_gamma_t = safe_sqrt(1.0 - sin_gamma_t * sin_gamma_t);
+        let gamma_t = safe_asin(sin_gamma_t);
+        let distance = 2.0 * cos_gamma_t / cos_theta_t;
+        let t = Vec3(f32::exp(-hair.sigma_a.x() * distance), f32::exp(-hair.sigma_a.y() * distance), f32::exp(-hair.sigma_a.z() * distance));
+        let ap = ap(cos_theta_o, eta, h, t);
+        let weights = ap.map(|a| (a.x() + a.y() + a.z()) / 3.0);
+        let total: f32 = weights.iter().sum();
+        let ap_pdf = weights.map(|w| if total > 0.0 { w / total } else { 1.0 / (P_MAX + 1) as f32 });
+
+        let beta_m = hair.beta_m;
+        let v0 = (0.726 * beta_m + 0.812 * beta_m * beta_m + 3.7 * beta_m.powi(20)).powi(2);
+        let beta_n = hair.beta_n;
+        let s = f32::sqrt(PI / 8.0) * (0.265 * beta_n + 1.194 * beta_n * beta_n + 5.372 * beta_n.powi(22));
+
+        let mut sin_2k_alpha = [f32::sin(hair.alpha.to_radians()), 0.0, 0.0];
+        let mut cos_2k_alpha = [safe_sqrt(1.0 - sin_2k_alpha[0] * sin_2k_alpha[0]), 0.0, 0.0];
+        for i in 1..3 {
+            sin_2k_alpha[i] = 2.0 * cos_2k_alpha[i - 1] * sin_2k_alpha[i - 1];
+            cos_2k_alpha[i] = cos_2k_alpha[i - 1].powi(2) - sin_2k_alpha[i - 1].powi(2);
+        }
+
+        HairBsdf {
+            frame,
+            gamma_o: safe_asin(h),
+            gamma_t,
+            sin_theta_o,
+            cos_theta_o,
+            phi_o,
+            v: [v0, 0.25 * v0, 4.0 * v0, 4.0 * v0],
+            s,
+            sin_2k_alpha,
+            cos_2k_alpha,
+            ap,
+            ap_pdf
+        }
+    }
+
+    /// sin and cos of the angle of `wo` to the normal plane, tilted by the
+    /// scales for scattering order `p`: R is tilted by -2 alpha, TT by alpha,
+    /// and TRT by 4 alpha.
+    fn tilted(&self, p: usize) -> (f32, f32) {
+        let (sin, cos) = (self.sin_theta_o, self.cos_theta_o);
+        match p {
+            0 => (sin * self.cos_2k_alpha[1] - cos * self.sin_2k_alpha[1], cos * self.cos_2k_alpha[1] + sin * self.sin_2k_alpha[1]),
+            1 => (sin * self.cos_2k_alpha[0] + cos * self.sin_2k_alpha[0], cos * self.cos_2k_alpha[0] - sin * self.sin_2k_alpha[0]),
+            2 => (sin * self.cos_2k_alpha[2] + cos * self.sin_2k_alpha[2], cos * self.cos_2k_alpha[2] - sin * self.sin_2k_alpha[2]),
+            _ => (sin, cos)
+        }
+    }
+
+    /// The sum over scattering orders of their lobes, each weighted by `weight`.
+    fn lobes<T, F>(&self, direction: Vec3, zero: T, weight: F) -> T
+    where
+        T: std::ops::Add<Output = T> + std::ops::Mul<f32, Output = T>,
+        F: Fn(usize) -> T
+    {
+        let wi = self.frame.to_local(direction.unit_vector());
+        let sin_theta_i = wi.x();
+        let cos_theta_i = safe_sqrt(1.0 - sin_theta_i * sin_theta_i);
+        let phi_difference = f32::atan2(wi.z(), wi.y()) - self.phi_o;
+
+        let mut sum = zero;
+        for p in 0..P_MAX {
+            let (sin_theta_op, cos_theta_op) = self.tilted(p);
+            let m = mp(cos_theta_i, cos_theta_op.abs(), sin_theta_i, sin_theta_op, self.v[p]);
+            sum = sum + weight(p) * (m * np(phi_difference, p, self.s, self.gamma_o, self.gamma_t));
+        }
+        let m = mp(cos_theta_i, self.cos_theta_o, sin_theta_i, self.sin_theta_o, self.v[P_MAX]);
+        sum + weight(P_MAX) * (m / (2.0 * PI))
+    }
+}
+
+impl Pdf for HairBsdf {
+    fn value(&self, direction: Vec3) -> f32 {
+        self.lobes(direction, 0.0, |p| self.ap_pdf[p])
+    }
+
+    /// Picks a scattering order by its attenuation, then samples its
+    /// longitudinal and azimuthal lobes.
+    fn generate(&self) -> Vec3 {
+        let mut u = random::<f32>();
+        let mut p = 0;
+        while p < P_MAX && u >= self.ap_pdf[p] {
+            u -= self.ap_pdf[p];
+            p += 1;
+        }
+
+        let (sin_theta_op, cos_theta_op) = self.tilted(p);
+        let u0 = random::<f32>().max(1e-5);
+        let v = self.v[p];
+        let cos_theta = 1.0 + v * f32::ln(u0 + (1.0 - u0) * f32::exp(-2.0 / v));
+        let sin_theta = safe_sqrt(1.0 - cos_theta * cos_theta);
+        let cos_phi = f32::cos(2.0 * PI * random::<f32>());
+        let sin_theta_i = -cos_theta * sin_theta_op + sin_theta * cos_phi * cos_theta_op;
+        let cos_theta_i = safe_sqrt(1.0 - sin_theta_i * sin_theta_i);
+
+        let dphi = if p < P_MAX {
+            phi(p, self.gamma_o, self.gamma_t) + sample_trimmed_logistic(random::<f32>(), self.s, -PI, PI)
+        } else {
+            2.0 * PI * random::<f32>()
+        };
+        let phi_i = self.phi_o + dphi;
+        self.frame.local(Vec3(sin_theta_i, cos_theta_i * phi_i.cos(), cos_theta_i * phi_i.sin()))
+    }
+}
+
+impl Bsdf for HairBsdf {
+    /// The model already includes the cosine factor, which pbrt divides out.
+    fn eval(&self, direction: Vec3) -> Color {
+        self.lobes(direction, Vec3(0.0, 0.0, 0.0), |p| self.ap[p])
+    }
+}
+
+impl Material for Hair {
+    fn scatter(&self, r_in: Ray, record: HitRecord) -> Option<Scatter> {
+        let h = -1.0 + 2.0 * record.v;
+        Some(Scatter::Bsdf(Box::new(HairBsdf::new(self, h, record.frame(), -r_in.direction().unit_vector()))))
+    }
+}
@@ -0,0 +1,181 @@
+use crate::vec3::{Vec3, Point3D};
+use crate::ray::Ray;
+use crate::hit::{self, HitRecord, Hittable};
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::texture::ImageTexture;
+
+/// A terrain over a grid of `columns` by `rows` heights, stored row by row.
+/// The grid covers `size.x()` along x and `size.z()` along z from `minimum`,
+/// and heights are scaled by `size.y()` above it.
+///
+/// Each cell of the grid is split into two triangles. Hits are found by
+/// walking the cells under the ray in order, with a 2D DDA, so that only a
+/// few cells are tested. Normals are interpolated across the triangles from
+/// the slopes at the grid points, for smooth shading. u and v go along x and
+/// z across the whole grid.
+pub struct Heightfield {
+    minimum: Point3D,
+    size: Vec3,
+    columns: usize,
+    rows: usize,
+    heights: Vec<f32>,
+    bounds: Aabb,
+    material: Box<dyn Material>
+}
+
+impl Heightfield {
+    /// A heightfield over a grid of at least 2 by 2 `heights`.
+    pub fn new(minimum: Point3D, size: Vec3, columns: usize, rows: usize, heights: Vec<f32>, material: Box<dyn Material>) -> Self {
+        assert!(columns >= 2 && rows >= 2 && heights.len() == columns * rows, "invalid heightfield grid");
+        let (low, high) = heights.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(low, high), &h| (low.min(h), high.max(h)));
+        let bounds = Aabb {
+            minimum: minimum + Vec3(0.0, low * size.y() - 1e-4, 0.0),
+            maximum: minimum + Vec3(size.x(), high * size.y() + 1e-4, size.z())
+        };
+        Heightfield { minimum, size, columns, rows, heights, bounds, material }
+    }
+
+    /// A heightfield with a grid point per pixel of `image`, whose heights are
+    /// the brightness of the pixels. The top row of the image is at the
+    /// smallest z.
+    pub fn from_image(image: &ImageTexture, minimum: Point3D, size: Vec3, material: Box<dyn Material>) -> Self {
+        let heights = image.pixels.iter().map(|c| 0.2126 * c.x() + 0.7152 * c.y() + 0.0722 * c.z()).collect();
+        Heightfield::new(minimum, size, image.width, image.height, heights, material)
+    }
+
+    /// The size of a cell along x and z.
+    fn cell_size(&self) -> (f32, f32) {
+        (self.size.x() / (self.columns - 1) as f32, self.size.z() / (self.rows - 1) as f32)
+    }
+
+    /// The height above `minimum` of the grid point at column `i` and row `j`,
+    /// clamped to the grid.
+    fn height(&self, i: isize, j: isize) -> f32 {
+        let i = i.clamp(0, self.columns as isize - 1) as usize;
+        let j = j.clamp(0, self.rows as isize - 1) as usize;
+        self.heights[j * self.columns + i] * self.size.y()
+    }
+
+    fn point(&self, i: usize, j: usize) -> Point3D {
+        let (dx, dz) = self.cell_size();
+        self.minimum + Vec3(i as f32 * dx, self.height(i as isize, j as isize), j as f32 * dz)
+    }
+
+    /// The unit normal at the grid point at column `i` and row `j`, from the
+    /// slopes to its neighbours.
+    fn normal(&self, i: usize, j: usize) -> Vec3 {
+        let (dx, dz) = self.cell_size();
+        let (i, j) = (i as isize, j as isize);
+        let slope_x = (self.height(i + 1, j) - self.height(i - 1, j)) / (2.0 * dx);
+        let slope_z = (self.height(i, j + 1) - self.height(i, j - 1)) / (2.0 * dz);
+        Vec3(-slope_x, 1.0, -slope_z).unit_vector()
+    }
+
+    /// The hit of `r` within [`t_min`, `t_max`] with the cell at column `i`
+    /// and row `j`, if any.
+    fn hit_cell(&self, r: Ray, i: usize, j: usize, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let corners = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+        // Both triangles go around the same way, so that their normals point up.
+        let mut closest: Option<HitRecord> = None;
+        for triangle in [[corners[0], corners[2], corners[1]], [corners[0], corners[3], corners[2]]] {
+            let t_max = closest.map_or(t_max, |record| record.t);
+            if let Some(record) = self.hit_triangle(r, triangle, t_min, t_max) {
+                closest = Some(record);
+            }
+        }
+        closest
+    }
+
+    /// The hit of `r` with the triangle between the grid points `vertices`,
+    /// with the Möller-Trumbore algorithm.
+    fn hit_triangle(&self, r: Ray, vertices: [(usize, usize); 3], t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let [p0, p1, p2] = vertices.map(|(i, j)| self.point(i, j));
+        let (edge1, edge2) = (p1 - p0, p2 - p0);
+        let pvec = r.direction().cross(edge2);
+        let determinant = edge1.dot(pvec);
+        if determinant.abs() < 1e-12 {
+            return None;
+        }
+        let inv_determinant = 1.0 / determinant;
+        let tvec = r.origin() - p0;
+        let b1 = tvec.dot(pvec) * inv_determinant;
+        if !(0.0..=1.0).contains(&b1) {
+            return None;
+        }
+        let qvec = tvec.cross(edge1);
+        let b2 = r.direction().dot(qvec) * inv_determinant;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return None;
+        }
+        let t = edge2.dot(qvec) * inv_determinant;
+        if t < t_min || t > t_max {
+            return None;
+        }
+
+        let p = r.at(t);
+        let u = (p.x() - self.minimum.x()) / self.size.x();
+        let v = (p.z() - self.minimum.z()) / self.size.z();
+        let [n0, n1, n2] = vertices.map(|(i, j)| self.normal(i, j));
+        let smooth_normal = (n0 * (1.0 - b1 - b2) + n1 * b1 + n2 * b2).unit_vector();
+
+        // Which side is hit is decided by the flat triangle, and the shading
+        // normal is the smooth one, turned to the same side.
+        let record = HitRecord::new(r, t, edge1.cross(edge2).unit_vector(), u, v, Vec3(1.0, 0.0, 0.0), &*self.material);
+        let normal = if record.front_face { smooth_normal } else { -smooth_normal };
+        let (tangent, bitangent) = hit::tangent_frame(normal, Vec3(1.0, 0.0, 0.0));
+        Some(HitRecord { normal, tangent, bitangent, ..record })
+    }
+}
+
+impl Hittable for Heightfield {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let (t_start, t_end) = self.bounding_box(r.time(), r.time())?.clip(r, t_min, t_max)?;
+        let (dx, dz) = self.cell_size();
+        let (cells_x, cells_z) = (self.columns as isize - 1, self.rows as isize - 1);
+
+        // The cell where the ray enters the grid, in grid coordinates.
+        let start = r.at(t_start) - self.minimum;
+        let (gx, gz) = (start.x() / dx, start.z() / dz);
+        let mut i = (gx.floor() as isize).clamp(0, cells_x - 1);
+        let mut j = (gz.floor() as isize).clamp(0, cells_z - 1);
+
+        // Along each axis: the cell step, the t between grid lines, and the t
+        // of the next grid line.
+        let axis = |g: f32, cell: isize, d: f32, size: f32| -> (isize, f32, f32) {
+            if d > 0.0 {
+                (1, size / d, t_start + ((cell + 1) as f32 - g) * size / d)
+            } else if d < 0.0 {
+                (-1, -size / d, t_start + (cell as f32 - g) * size / d)
+            } else {
+                (0, f32::INFINITY, f32::INFINITY)
+            }
+        };
+        let (step_i, delta_x, mut next_x) = axis(gx, i, r.direction().x(), dx);
+        let (step_j, delta_z, mut next_z) = axis(gz, j, r.direction().z(), dz);
+
+        loop {
+            // The triangles of a cell are over it, so its hits are the closest.
+            if let Some(record) = self.hit_cell(r, i as usize, j as usize, t_min, t_max) {
+                return Some(record);
+            }
+            if next_x.min(next_z) >= t_end {
+                return None;
+            }
+            if next_x < next_z {
+                i += step_i;
+                next_x += delta_x;
+            } else {
+                j += step_j;
+                next_z += delta_z;
+            }
+            if i < 0 || i >= cells_x || j < 0 || j >= cells_z {
+                return None;
+            }
+        }
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        Some(self.bounds)
+    }
+}
@@ -1,6 +1,9 @@
-use crate::vec3::Vec3;
+use crate::vec3::{Vec3, Point3D};
 use crate::ray::Ray;
 use crate::material::Material;
+use crate::onb::Onb;
+use crate::aabb::Aabb;
+use rand::Rng;
 
 #[derive(Clone, Copy)]
 pub struct HitRecord<'a> {
@@ -8,33 +11,134 @@ pub struct HitRecord<'a> {
     pub normal: Vec3,
     pub front_face: bool,
     pub t: f32,
+    /// Surface coordinates of the hit point, in [0, 1].
+    pub u: f32,
+    pub v: f32,
+    /// Unit tangent along increasing `u`, and bitangent, which complete the
+    /// shading frame around `normal`. Anisotropic materials are aligned on them.
+    pub tangent: Vec3,
+    pub bitangent: Vec3,
     pub material: &'a dyn Material
 }
 
+impl<'a> HitRecord<'a> {
+    /// A hit at `t` along `r`, on a surface with `outward_normal` (unit length)
+    /// at surface coordinates (`u`, `v`). The normal is flipped to face the ray,
+    /// and the tangent frame follows `dpdu`.
+    pub fn new(r: Ray, t: f32, outward_normal: Vec3, u: f32, v: f32, dpdu: Vec3, material: &'a dyn Material) -> Self {
+        let front_face = r.direction().dot(outward_normal) < 0.0;
+        let normal = if front_face { outward_normal } else { -outward_normal };
+        let (tangent, bitangent) = tangent_frame(normal, dpdu);
+        HitRecord { p: r.at(t), normal, front_face, t, u, v, tangent, bitangent, material }
+    }
+
+    /// The shading frame at the hit point: the tangent, the bitangent and the normal.
+    pub fn frame(&self) -> Onb {
+        Onb { u: self.tangent, v: self.bitangent, w: self.normal }
+    }
+}
+
+/// The tangent and bitangent at a point with unit `normal`, from `dpdu`, the
+/// derivative of the point along `u`. `dpdu` is made orthogonal to the normal.
+/// Where it vanishes, like at the poles of a sphere, any tangent is picked.
+pub fn tangent_frame(normal: Vec3, dpdu: Vec3) -> (Vec3, Vec3) {
+    let tangent = dpdu - normal * dpdu.dot(normal);
+    if tangent.length_squared() < 1e-12 {
+        let frame = Onb::build_from_w(normal);
+        return (frame.u, frame.v);
+    }
+    let tangent = tangent.unit_vector();
+    (tangent, normal.cross(tangent))
+}
+
+/// A stretch of a ray inside a solid, between the surface where it enters and
+/// the one where it leaves. `enter` faces the outside, and `exit` the inside.
+#[derive(Clone, Copy)]
+pub struct Span<'a> {
+    pub enter: HitRecord<'a>,
+    pub exit: HitRecord<'a>
+}
+
 pub trait Hittable {
-    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord>;
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>>;
+
+    /// A box containing the object over the time interval [`time0`, `time1`].
+    /// `None` when there is nothing to bound, like an empty list.
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb>;
+
+    /// The spans of the whole line of `r`, negative `t` included, inside this
+    /// object, in order along the ray. Only closed objects have an inside, so
+    /// only they can be combined with constructive solid geometry.
+    ///
+    /// By default, the spans are found by walking the ray from hit to hit.
+    fn spans(&self, r: Ray) -> Vec<Span<'_>> {
+        let mut spans = Vec::new();
+        let mut enter = None;
+        let mut t_min = f32::NEG_INFINITY;
+        while let Some(record) = self.hit(r, t_min, f32::INFINITY) {
+            match (enter, record.front_face) {
+                (None, true) => enter = Some(record),
+                (Some(enter_record), false) => {
+                    spans.push(Span { enter: enter_record, exit: record });
+                    enter = None;
+                }
+                _ => {}
+            }
+            // The step grows with t, so that it is never lost in rounding.
+            t_min = record.t + 1e-4 * record.t.abs().max(1.0);
+        }
+        spans
+    }
+
+    /// Density, with respect to solid angle, of sampling direction `v`
+    /// from `origin` with `random`. Only hittables used as lights need
+    /// to implement it.
+    fn pdf_value(&self, _origin: Point3D, _v: Vec3) -> f32 {
+        0.0
+    }
+
+    /// A random direction from `origin` towards this hittable.
+    fn random(&self, _origin: Point3D) -> Vec3 {
+        Vec3(1.0, 0.0, 0.0)
+    }
 }
 
 
-/// Implement hittable_list and hittable_list::hit. The book declares 
-/// a class that delegates to a vector container. Here we just implement 
+pub type HittableList = Vec<Box<dyn Hittable>>;
+
+/// Implement hittable_list and hittable_list::hit. The book declares
+/// a class that delegates to a vector container. Here we just implement
 /// the Hittable trait for Rust's Vec.
 /// TODO Do we need shared_ptr semantics (Rc, Arc) at all?
 impl Hittable for Vec<Box<dyn Hittable>> {
-        fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
         let mut closest_so_far = t_max;
         let mut hit_record: Option<HitRecord> = None;
 
         for object in self {
-            let has_hit = object.hit(r, t_min, closest_so_far);
-            match has_hit {
-                None => hit_record = hit_record,
-                Some(record) => { 
-                    hit_record = Some(record);
-                    closest_so_far = record.t;
-                }
+            if let Some(record) = object.hit(r, t_min, closest_so_far) {
+                hit_record = Some(record);
+                closest_so_far = record.t;
             }
         }
-        return hit_record;
+        hit_record
+    }
+
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb> {
+        self.iter()
+            .map(|object| object.bounding_box(time0, time1))
+            .reduce(|a, b| Some(Aabb::surrounding_box(a?, b?)))?
+    }
+
+    /// Objects in the list are sampled uniformly, so the density
+    /// is the average of the densities of each object.
+    fn pdf_value(&self, origin: Point3D, v: Vec3) -> f32 {
+        let weight = 1.0 / self.len() as f32;
+        self.iter().map(|object| weight * object.pdf_value(origin, v)).sum()
+    }
+
+    fn random(&self, origin: Point3D) -> Vec3 {
+        let index = rand::thread_rng().gen_range(0..self.len());
+        self[index].random(origin)
     }
 }
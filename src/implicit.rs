@@ -0,0 +1,97 @@
+use crate::vec3::{Vec3, Point3D};
+use crate::ray::Ray;
+use crate::hit::{HitRecord, Hittable};
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::sphere::get_sphere_uv;
+
+/// Bisection steps refining a root, which halve the bracket each time.
+const BISECTION_STEPS: u32 = 32;
+/// Offset of the finite differences taken for normals.
+const GRADIENT_DELTA: f32 = 1e-4;
+/// The shortest step along rays, so that they get past points where
+/// `function` is almost 0 without crossing the surface.
+const MIN_STEP: f32 = 1e-4;
+/// How far from their start rays moving away from the surface ignore sign
+/// changes.
+const LEAVING_DISTANCE: f32 = 4.0 * MIN_STEP;
+
+/// The surface where `function` is 0, inside `bounds`. The outside is where
+/// `function` is positive.
+///
+/// `lipschitz` bounds how fast `function` changes inside `bounds`: for any
+/// points p and q there, |f(p) - f(q)| <= `lipschitz` |p - q|. So no root is
+/// closer to a point than |f| / `lipschitz`, and rays step by that much, which
+/// never steps over the surface, then bisect the step where `function` changes
+/// sign. Only parts of the surface thinner than `MIN_STEP`, where the steps
+/// stop shrinking, can be missed. Normals are the gradient of `function`, by
+/// finite differences.
+pub struct ImplicitSurface {
+    pub function: Box<dyn Fn(Point3D) -> f32>,
+    pub bounds: Aabb,
+    pub lipschitz: f32,
+    pub material: Box<dyn Material>
+}
+
+impl ImplicitSurface {
+    /// The unit gradient of `function` at `p`, by central differences.
+    fn normal(&self, p: Point3D) -> Vec3 {
+        let h = GRADIENT_DELTA;
+        let d = |offset: Vec3| (self.function)(p + offset) - (self.function)(p - offset);
+        Vec3(d(Vec3(h, 0.0, 0.0)), d(Vec3(0.0, h, 0.0)), d(Vec3(0.0, 0.0, h))).unit_vector()
+    }
+
+    /// The root of `function` along `r` between `t0` and `t1`, where it has
+    /// the values `f0` and `f1` of opposite signs.
+    fn bisect(&self, r: Ray, (mut t0, mut t1): (f32, f32), f0: f32) -> f32 {
+        for _ in 0..BISECTION_STEPS {
+            let t = 0.5 * (t0 + t1);
+            if ((self.function)(r.at(t)) > 0.0) == (f0 > 0.0) {
+                t0 = t;
+            } else {
+                t1 = t;
+            }
+        }
+        0.5 * (t0 + t1)
+    }
+}
+
+impl Hittable for ImplicitSurface {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let (t_start, t_end) = self.bounds.clip(r, t_min, t_max)?;
+        let length = r.direction().length();
+
+        let mut t0 = t_start;
+        let mut f0 = (self.function)(r.at(t0));
+
+        // Reflected and refracted rays start next to the surface they leave,
+        // where `function` is almost 0, and could find it again just after
+        // their start. Rays moving away from the surface, along which |f|
+        // grows, skip sign changes close to their start.
+        let p = r.at(t_start);
+        let direction = r.direction() / length;
+        let slope = (self.function)(p + direction * GRADIENT_DELTA) - (self.function)(p - direction * GRADIENT_DELTA);
+        let leaving = (slope > 0.0) == (f0 > 0.0);
+
+        while t0 < t_end {
+            let step = (f0.abs() / self.lipschitz).max(MIN_STEP);
+            let t1 = (t0 + step / length).min(t_end);
+            let f1 = (self.function)(r.at(t1));
+            let near_start = (t1 - t_start) * length <= LEAVING_DISTANCE;
+            if (f0 > 0.0) != (f1 > 0.0) && !(leaving && near_start) {
+                let t = self.bisect(r, (t0, t1), f0);
+                let outward_normal = self.normal(r.at(t));
+                let (u, v) = get_sphere_uv(outward_normal);
+                let dpdu = Vec3(outward_normal.z(), 0.0, -outward_normal.x());
+                return Some(HitRecord::new(r, t, outward_normal, u, v, dpdu, &*self.material));
+            }
+            t0 = t1;
+            f0 = f1;
+        }
+        None
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        Some(self.bounds)
+    }
+}
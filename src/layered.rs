@@ -0,0 +1,111 @@
+use crate::vec3::{Vec3, Color};
+use crate::ray::Ray;
+use crate::hit::HitRecord;
+use crate::material::{Material, Scatter};
+use crate::bsdf::Bsdf;
+use crate::pdf::Pdf;
+use crate::texture::Texture;
+use crate::microfacet::fresnel_dielectric;
+use rand::random;
+
+/// A blend of two materials: `b` with probability `weight`, read from the
+/// first channel of its texture, and `a` otherwise. One of them is picked at
+/// each scatter, so the blend costs no more than a single material.
+pub struct MixMaterial {
+    pub a: Box<dyn Material>,
+    pub b: Box<dyn Material>,
+    pub weight: Box<dyn Texture>
+}
+
+/// A smooth clear coat of index `index_of_refraction` over a `base` material,
+/// like varnish or the top layer of car paint.
+///
+/// Light is reflected by the coat with the Fresnel reflectance towards the
+/// viewer, and reaches the base otherwise. Light leaving the base is scaled by
+/// the Fresnel transmittance of the coat. Inter-reflections between the coat
+/// and the base are ignored.
+pub struct CoatedMaterial {
+    pub base: Box<dyn Material>,
+    pub index_of_refraction: f32
+}
+
+/// The BSDF of the base of a `CoatedMaterial`, seen through the coat.
+struct CoatedBsdf {
+    base: Box<dyn Bsdf>,
+    normal: Vec3,
+    index_of_refraction: f32
+}
+
+impl MixMaterial {
+    fn weight(&self, record: &HitRecord) -> f32 {
+        self.weight.value(record.u, record.v, record.p).x().clamp(0.0, 1.0)
+    }
+}
+
+impl Material for MixMaterial {
+    fn scatter(&self, r_in: Ray, record: HitRecord) -> Option<Scatter> {
+        if random::<f32>() < self.weight(&record) {
+            self.b.scatter(r_in, record)
+        } else {
+            self.a.scatter(r_in, record)
+        }
+    }
+
+    fn emitted(&self, r_in: Ray, record: HitRecord) -> Color {
+        let weight = self.weight(&record);
+        self.a.emitted(r_in, record) * (1.0 - weight) + self.b.emitted(r_in, record) * weight
+    }
+}
+
+impl Pdf for CoatedBsdf {
+    fn value(&self, direction: Vec3) -> f32 {
+        self.base.value(direction)
+    }
+
+    fn generate(&self) -> Vec3 {
+        self.base.generate()
+    }
+}
+
+impl Bsdf for CoatedBsdf {
+    fn eval(&self, direction: Vec3) -> Color {
+        let cos_theta = direction.unit_vector().dot(self.normal).abs();
+        self.base.eval(direction) * (1.0 - fresnel_dielectric(cos_theta, self.index_of_refraction))
+    }
+}
+
+impl Material for CoatedMaterial {
+    /// The coat is picked with its reflectance towards the viewer, so that
+    /// the base is reached with the transmittance and needs no extra weight.
+    /// From inside, like in a coated glass, only the base is seen.
+    fn scatter(&self, r_in: Ray, record: HitRecord) -> Option<Scatter> {
+        if !record.front_face {
+            return self.base.scatter(r_in, record);
+        }
+
+        let unit_direction = r_in.direction().unit_vector();
+        let cos_theta = (-unit_direction).dot(record.normal);
+        if random::<f32>() < fresnel_dielectric(cos_theta, self.index_of_refraction) {
+            let reflected = Ray(record.p, unit_direction.reflect(record.normal), r_in.time(), r_in.wavelength());
+            return Some(Scatter::Specular { r: reflected, attenuation: Vec3(1.0, 1.0, 1.0) });
+        }
+
+        let transmittance = |direction: Vec3| {
+            let cos_theta = direction.unit_vector().dot(record.normal);
+            1.0 - fresnel_dielectric(cos_theta.abs(), self.index_of_refraction)
+        };
+        match self.base.scatter(r_in, record)? {
+            Scatter::Specular { r, attenuation } => {
+                let attenuation = attenuation * transmittance(r.direction());
+                Some(Scatter::Specular { r, attenuation })
+            }
+            Scatter::Bsdf(base) => {
+                Some(Scatter::Bsdf(Box::new(CoatedBsdf { base, normal: record.normal, index_of_refraction: self.index_of_refraction })))
+            }
+        }
+    }
+
+    fn emitted(&self, r_in: Ray, record: HitRecord) -> Color {
+        self.base.emitted(r_in, record)
+    }
+}
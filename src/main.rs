@@ -5,15 +5,84 @@ mod moving_sphere;
 mod sphere;
 mod camera;
 mod material;
+mod onb;
+mod pdf;
+mod aarect;
+mod background;
+mod scene;
+mod bsdf;
+mod texture;
+mod microfacet;
+mod principled;
+mod conductor;
+mod spectrum;
+mod thin_film;
+mod layered;
+mod oren_nayar;
+mod medium;
+mod perlin;
+mod bump;
+mod alpha;
+mod aabb;
+mod polynomial;
+mod plane;
+mod cylinder;
+mod torus;
+mod csg;
+mod cuboid;
+mod sdf;
+mod quadric;
+mod implicit;
+mod heightfield;
+mod curve;
+mod hair;
+mod mesh;
+mod subdivision;
+mod bvh;
+mod triangle_mesh;
+mod displacement;
+mod metaballs;
 
-use hit::{HitRecord, Hittable};
+use hit::{Hittable, HittableList};
 use vec3::{Vec3,Color};
 use ray::Ray;
 use moving_sphere::MovingSphere;
 use sphere::Sphere;
+use aarect::{XyRect, XzRect, YzRect};
 use camera::Camera;
 use rand::{Rng, random};
-use material::{Dielectric, Lambertian, Metal};
+use std::rc::Rc;
+use material::{Material, Dielectric, Dispersion, DiffuseLight, Lambertian, Metal, Scatter};
+use background::{Background, EnvironmentMap};
+use scene::Scene;
+use bvh::Bvh;
+use texture::{Texture, SolidColor, CheckerTexture, ScaledTexture, NoiseTexture, ImageTexture, solid};
+use microfacet::{RoughConductor, RoughDielectric, AnisotropicConductor};
+use principled::Principled;
+use conductor::{Conductor, CONDUCTOR_PRESETS};
+use thin_film::{ThinFilm, Substrate};
+use layered::{MixMaterial, CoatedMaterial};
+use oren_nayar::OrenNayar;
+use perlin::Perlin;
+use bump::{NormalMap, BumpMap};
+use alpha::{AlphaMasked, AlphaMode};
+use plane::{Plane, Disk};
+use cylinder::{Cylinder, Cone};
+use torus::Torus;
+use csg::{Union, Intersection, Difference};
+use cuboid::Cuboid;
+use sdf::{SdfObject, SdfSphere, RoundBox, Mandelbulb, SmoothUnion, Twist, Repeat};
+use aabb::Aabb;
+use quadric::Quadric;
+use implicit::ImplicitSurface;
+use heightfield::Heightfield;
+use curve::{Curve, CurveType};
+use hair::Hair;
+use mesh::PolygonMesh;
+use triangle_mesh::TriangleMesh;
+use displacement::Displacement;
+use metaballs::{Metaballs, Ball};
+use medium::{HomogeneousMedium, MediumEvent, IsotropicPhase, ConstantMedium, Subsurface};
 
 fn clamp(x: f32, min: f32, max: f32) -> f32 {
     if x < min {
@@ -41,85 +110,155 @@ fn write_color(pixel_color: Color, samples_per_pixel: u32) {
              (c * clamp(b, 0.0, 0.999)) as i32)
 }
 
-/// Returns the position `t` along a ray `r`, if `r` hits the inside of a sphere.
-/// 
-/// If a ray P(t) hits the sphere centered in `center`, there exists t for which 
-/// (P(t) - C) * (P(t) - C) = radius^2.
+/// Computes the color seen along a ray.
 ///
-/// Where C = (C_x, C_y, C_z) is the vector representing the `center` of the sphere.
-/// 
-/// To determine if `r` hits the sphere we need to plug in 
-/// the definition of P(t) = A + t*b, do some algebra, and find to roots of:
+/// The path is traced iteratively: `throughput` holds the product of the
+/// attenuations met so far, and each light contribution found along the way
+/// is scaled by it.
 ///
-/// t^2 (b*b) + 2*t*b * (A-C) + (A-C) * (A-C) - radius^2 = 0
+/// Specular bounces are followed as they are. At diffuse bounces the light
+/// reaching the hit point is estimated twice: once by aiming a ray at the
+/// scene lights (and at the background, when it emits), and once by following
+/// a ray drawn from the material density. Both estimates are combined with
+/// multiple importance sampling, so that each strategy is trusted where it
+/// is good at finding light: light sampling for small lights and rough
+/// surfaces, material sampling for large lights and glossy surfaces.
+///
+/// In spectral mode, the ray carries a single wavelength: every color met along
+/// the path is upsampled to its spectrum and read at that wavelength, and the
+/// result holds the radiance at that wavelength on all three channels.
+/// Each path traces one wavelength, not hero wavelengths: color noise only
+/// falls with the number of samples per pixel, and is worst in scenes where
+/// most light passes through dispersive glass.
+///
+/// Inside objects filled with a medium, the ray may scatter before reaching
+/// the next surface. Scattering in the medium is treated like a diffuse
+/// bounce, with the phase function in place of the BSDF.
+///
+/// After `roulette_depth` bounces, paths are terminated with Russian roulette:
+/// a path survives with a probability that follows its throughput, and the
+/// survivors are divided by that probability, so the result stays unbiased.
 ///
-/// # Arguments
-/// - `center`: the center of the sphere
-/// - `radius`: the radius of the sphere
-/// - `r`: a ray
-fn hit_sphere(center: Vec3, radius: f32, r: Ray) -> f32 {
-    let oc: Vec3 = r.origin() - center;
-    let a = r.direction().length_squared();
-    let half_b = oc.dot(r.direction());
-    let c = oc.length_squared() - radius * radius;
-    let discriminant = half_b * half_b - a * c;
-    if discriminant < 0.0 {
-        // t
-        return -1.0
-    } 
-    // t
-    (- half_b - discriminant.sqrt()) / a
-}
-
-
-/// Renders surface normals on a sphere.
-/// 
-/// Linearly blends white and blue depending on the height of the 𝑦 coordinate 
-/// after scaling the ray direction to unit length (so −1.0<𝑦<1.0). 
-/// Because we're looking at the 𝑦 height after normalizing the vector, 
-/// you'll notice a horizontal gradient to the color in addition to the vertical gradient.
-/// 
 /// # Arguments
 ///
 /// - `r`: a struct defining origin and direction of  a ray.
-/// - `world`: a sphere implementing the `Hittable` interface. 
-/// - `depth`: max depth allowed when calling ray_color recursively.
-fn ray_color<T: Hittable>(r: Ray, world: &T, depth: u32) -> Vec3 {
-    if depth <= 0 {
-        return Vec3(0.0, 0.0, 0.0)
-    }
-
-    // Ignore hits very near zero to fix shadow acne.
-    let has_hit: Option<HitRecord> = world.hit(r, 0.001, f32::INFINITY);
- 
-    match has_hit {
-        Some(record) => {
-            match record.material.scatter(r, record) {
-                Some(scattered) => 
-                    scattered.attenuation * ray_color(scattered.r, world, depth-1),
-                None => {
-                    Vec3(0.0, 0.0, 0.0)
+/// - `scene`: the objects, lights and background to render.
+/// - `max_depth`: max number of bounces along the path.
+/// - `roulette_depth`: number of bounces before Russian roulette starts.
+fn ray_color(r: Ray, scene: &Scene, max_depth: u32, roulette_depth: u32) -> Color {
+    let mut color = Vec3(0.0, 0.0, 0.0);
+    let mut throughput = Vec3(1.0, 1.0, 1.0);
+    let mut r = r;
+    // The material density the current ray was drawn from, if any.
+    let mut bsdf_pdf: Option<f32> = None;
+    // The medium the current ray travels through, if any.
+    let mut medium: Option<HomogeneousMedium> = None;
+    // Where the current ray was last scattered. Media interfaces crossed since
+    // then do not count, for the weights of multiple importance sampling.
+    let mut vertex = r.origin();
+    let wavelength = r.wavelength();
+    let spectral = |c: Color| -> Color {
+        if wavelength > 0.0 { spectrum::upsample(c, wavelength) } else { c }
+    };
+
+    for depth in 0..max_depth {
+        // Ignore hits very near zero to fix shadow acne.
+        let hit = scene.world.hit(r, 0.001, f32::INFINITY);
+
+        // In a medium, the ray may scatter before it reaches the next surface.
+        let mut medium_scattering = None;
+        if let Some(medium) = medium {
+            let length = r.direction().length();
+            let distance = hit.map_or(f32::INFINITY, |record| record.t * length);
+            match medium.sample(distance) {
+                MediumEvent::Scatter { distance, weight } => {
+                    throughput = throughput * weight;
+                    medium_scattering = Some(r.at(distance / length));
+                }
+                MediumEvent::Pass { weight } => throughput = throughput * weight
+            }
+        }
+
+        let from_vertex = Ray(vertex, r.direction(), r.time(), r.wavelength());
+        let (p, scatter, surface) = match (medium_scattering, hit) {
+            (Some(p), _) => (p, Some(Scatter::Bsdf(Box::new(IsotropicPhase))), None),
+            (None, Some(record)) if record.material.is_interface() => {
+                medium = if record.front_face { record.material.medium().map(|m| m.map(spectral)) } else { None };
+                r = Ray(record.p, r.direction(), r.time(), r.wavelength());
+                continue;
+            }
+            (None, Some(record)) => {
+                color = color + throughput * spectral(record.material.emitted(r, record)) * scene.mis_weight(from_vertex, bsdf_pdf);
+                (record.p, record.material.scatter(r, record), Some(record))
+            }
+            (None, None) => {
+                color = color + throughput * spectral(scene.background.value(r)) * scene.mis_weight(from_vertex, bsdf_pdf);
+                break;
+            }
+        };
+
+        match scatter {
+            None => break,
+            Some(Scatter::Specular { r: scattered, attenuation }) => {
+                throughput = throughput * spectral(attenuation);
+                r = scattered;
+                vertex = p;
+                bsdf_pdf = None;
+            }
+            Some(Scatter::Bsdf(bsdf)) => {
+                // Light sampling.
+                if let Some(light_pdf) = scene.light_pdf(p) {
+                    let to_light = Ray(p, light_pdf.generate(), r.time(), r.wavelength());
+                    let light_pdf_val = light_pdf.value(to_light.direction());
+                    let f = bsdf.eval(to_light.direction());
+                    if light_pdf_val > 0.0 && f.max_component() > 0.0 {
+                        let weight = pdf::power_heuristic(light_pdf_val, bsdf.value(to_light.direction()));
+                        let contribution = throughput * spectral(f) * scene.emission(to_light, medium, spectral) * weight / light_pdf_val;
+                        // Degenerate densities would turn the whole pixel to NaN.
+                        if light_pdf_val.is_finite() && contribution.is_finite() {
+                            color = color + contribution;
+                        }
+                    }
+                }
+
+                // Material sampling.
+                let scattered = Ray(p, bsdf.generate(), r.time(), r.wavelength());
+                let pdf_val = bsdf.value(scattered.direction());
+                if pdf_val <= 0.0 {
+                    break;
                 }
+                throughput = throughput * spectral(bsdf.eval(scattered.direction())) / pdf_val;
+                r = scattered;
+                vertex = p;
+                bsdf_pdf = Some(pdf_val);
+            }
+        }
+
+        // A ray through a surface enters the medium inside the object, or
+        // leaves it. Media are not nested.
+        if let Some(record) = surface {
+            if r.direction().dot(record.normal) < 0.0 {
+                medium = if record.front_face { record.material.medium().map(|m| m.map(spectral)) } else { None };
             }
         }
-        None => {
-            let unit_direction: Vec3 = r.direction().unit_vector();
-            let t = 0.5 * (unit_direction.y() + 1.0);
-            // Here Vec3(1.0, 1.0, 1.0) is the color white; and Vec3(0.5, 0.7, 1.0
-            // is the color blue. Both are expressed as RGB values.
-            // We scale 0.0 <= t <= 1 so that when t = 1.0 we get blue. When t = 0.0 we get white.
-            // In between, we get a linear blend.
-            Vec3(1.0, 1.0, 1.0) * (1.0 - t) + Vec3(0.5, 0.7, 1.0) * t
+
+        if depth + 1 >= roulette_depth {
+            let survival = f32::min(throughput.max_component(), 0.95);
+            if random::<f32>() >= survival {
+                break;
+            }
+            throughput = throughput / survival;
         }
     }
+    color
 }
 
-fn hittable_world_random_scene() -> Vec<Box<dyn Hittable>> {
+fn hittable_world_random_scene() -> Scene {
     let mut rng = rand::thread_rng();
-    let mut world: Vec<Box<dyn Hittable>> = vec![ 
-        Box::new(Sphere {
-            center: Vec3(0.0, -1000.0, 0.0),
-            radius: 1000.0,
+    let mut world: HittableList = vec![
+        Box::new(Plane {
+            point: Vec3(0.0, 0.0, 0.0),
+            normal: Vec3(0.0, 1.0, 0.0),
             material: Box::new(Lambertian {
                 albedo: Vec3(0.5, 0.5, 0.5)
             })
@@ -148,7 +287,7 @@ fn hittable_world_random_scene() -> Vec<Box<dyn Hittable>> {
                     world.push(Box::new(Sphere { center, radius, material }))
                 } else {
                     // glass
-                    let material = Box::new(Dielectric { index_of_refraction: 1.5 });
+                    let material = Box::new(Dielectric { index_of_refraction: 1.5, dispersion: None, absorption: Vec3(0.0, 0.0, 0.0) });
                     let radius = 0.2;
                     world.push(Box::new(Sphere { center, radius, material }))
                 }
@@ -161,7 +300,9 @@ fn hittable_world_random_scene() -> Vec<Box<dyn Hittable>> {
         center: Vec3(0.0, 1.0, 0.0),
         radius: 1.0,
         material: Box::new(Dielectric {
-            index_of_refraction: 1.5 
+            index_of_refraction: 1.5,
+            dispersion: None,
+            absorption: Vec3(0.0, 0.0, 0.0)
         })
     }));
 
@@ -181,45 +322,1152 @@ fn hittable_world_random_scene() -> Vec<Box<dyn Hittable>> {
            fuzz: 0.0
        })
     }));
-    world
+    Scene { world, lights: vec![], background: Background::Sky }
+}
+
+/// An empty Cornell box lit by a small ceiling light, with a metal and a glass sphere.
+///
+/// Only the ceiling light is sampled explicitly. Light samples aimed at the
+/// glass sphere would find no emission behind it, so its caustic on the floor
+/// comes from paths that reach the light by bouncing through it.
+fn cornell_box() -> Scene {
+    let red = Vec3(0.65, 0.05, 0.05);
+    let white = Vec3(0.73, 0.73, 0.73);
+    let green = Vec3(0.12, 0.45, 0.15);
+    let light = Vec3(15.0, 15.0, 15.0);
+
+    let world: HittableList = vec![
+        Box::new(YzRect { y0: 0.0, y1: 555.0, z0: 0.0, z1: 555.0, k: 555.0, material: Box::new(Lambertian { albedo: green }) }),
+        Box::new(YzRect { y0: 0.0, y1: 555.0, z0: 0.0, z1: 555.0, k: 0.0, material: Box::new(Lambertian { albedo: red }) }),
+        Box::new(XzRect { x0: 213.0, x1: 343.0, z0: 227.0, z1: 332.0, k: 554.0, material: Box::new(DiffuseLight { emit: light }) }),
+        Box::new(XzRect { x0: 0.0, x1: 555.0, z0: 0.0, z1: 555.0, k: 0.0, material: Box::new(Lambertian { albedo: white }) }),
+        Box::new(XzRect { x0: 0.0, x1: 555.0, z0: 0.0, z1: 555.0, k: 555.0, material: Box::new(Lambertian { albedo: white }) }),
+        Box::new(XyRect { x0: 0.0, x1: 555.0, y0: 0.0, y1: 555.0, k: 555.0, material: Box::new(Lambertian { albedo: white }) }),
+        Box::new(Sphere { center: Vec3(370.0, 90.0, 350.0), radius: 90.0, material: Box::new(Metal { albedo: Vec3(0.8, 0.85, 0.88), fuzz: 0.0 }) }),
+        // Tinted glass, that lets through a light blue across its diameter.
+        Box::new(Sphere { center: Vec3(190.0, 90.0, 190.0), radius: 90.0, material: Box::new(Dielectric {
+            index_of_refraction: 1.5,
+            dispersion: None,
+            absorption: Dielectric::absorption_from_transmittance(Vec3(0.6, 0.85, 0.95), 180.0)
+        }) }),
+    ];
+
+    // The material of a light shape is never used: lights are only sampled.
+    let lights: HittableList = vec![
+        Box::new(XzRect { x0: 213.0, x1: 343.0, z0: 227.0, z1: 332.0, k: 554.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    Scene { world, lights, background: Background::Solid(Vec3(0.0, 0.0, 0.0)) }
+}
+
+/// Rough metals and rough glass, with roughness increasing from left to right,
+/// lit by the sky and a small spherical light.
+fn microfacet_scene() -> Scene {
+    let gold = Vec3(1.0, 0.78, 0.34);
+    let light = Vec3(20.0, 20.0, 20.0);
+
+    let mut world: HittableList = vec![
+        Box::new(Plane { point: Vec3(0.0, 0.0, 0.0), normal: Vec3(0.0, 1.0, 0.0), material: Box::new(Lambertian { albedo: Vec3(0.5, 0.5, 0.5) }) }),
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+    for (i, roughness) in [0.0, 0.2, 0.4, 0.7].iter().enumerate() {
+        let x = -4.5 + 3.0 * i as f32;
+        world.push(Box::new(Sphere {
+            center: Vec3(x, 1.0, -1.5),
+            radius: 1.0,
+            material: Box::new(RoughConductor { albedo: Box::new(SolidColor { color: gold }), roughness: solid(*roughness) })
+        }));
+        world.push(Box::new(Sphere {
+            center: Vec3(x, 1.0, 1.5),
+            radius: 1.0,
+            material: Box::new(RoughDielectric { index_of_refraction: 1.5, roughness: solid(*roughness) })
+        }));
+    }
+    // Roughness driven by a texture: alternating polished and brushed checks.
+    world.push(Box::new(Sphere {
+        center: Vec3(0.0, 1.0, 4.5),
+        radius: 1.0,
+        material: Box::new(RoughConductor {
+            albedo: Box::new(SolidColor { color: Vec3(0.95, 0.93, 0.88) }),
+            roughness: Box::new(CheckerTexture { odd: solid(0.05), even: solid(0.5) })
+        })
+    }));
+
+    let lights: HittableList = vec![
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    Scene { world, lights, background: Background::Sky }
+}
+
+/// Principled materials: plastic, gold, car paint, velvet, tinted glass, wax,
+/// and a glTF metallic-roughness material with a checkered metallic-roughness map.
+fn principled_scene() -> Scene {
+    let light = Vec3(20.0, 20.0, 20.0);
+    let color = |r: f32, g: f32, b: f32| -> Box<dyn Texture> { Box::new(SolidColor { color: Vec3(r, g, b) }) };
+
+    let mut plastic = Principled::new(color(0.8, 0.1, 0.1));
+    plastic.roughness = solid(0.3);
+
+    let mut gold = Principled::new(color(1.0, 0.78, 0.34));
+    gold.metallic = solid(1.0);
+    gold.roughness = solid(0.35);
+
+    let mut car_paint = Principled::new(color(0.05, 0.1, 0.5));
+    car_paint.roughness = solid(0.6);
+    car_paint.clearcoat = solid(1.0);
+
+    let mut velvet = Principled::new(color(0.3, 0.05, 0.3));
+    velvet.roughness = solid(1.0);
+    velvet.sheen = solid(1.0);
+
+    let mut glass = Principled::new(color(0.8, 1.0, 0.8));
+    glass.roughness = solid(0.1);
+    glass.transmission = solid(1.0);
+
+    let mut wax = Principled::new(color(0.9, 0.8, 0.6));
+    wax.subsurface = solid(1.0);
+    wax.specular_tint = solid(0.5);
+
+    // Second channel is roughness, third is metallic.
+    let metallic_roughness = CheckerTexture { odd: color(0.0, 0.2, 1.0), even: color(0.0, 0.8, 0.0) };
+    let gltf = Principled::from_gltf_metallic_roughness(Vec3(0.9, 0.6, 0.5), None, 1.0, 1.0, Some(Box::new(metallic_roughness)));
+
+    let materials: Vec<Box<dyn Material>> = vec![
+        Box::new(plastic), Box::new(gold), Box::new(car_paint), Box::new(velvet),
+        Box::new(glass), Box::new(wax), Box::new(gltf)
+    ];
+
+    let mut world: HittableList = vec![
+        Box::new(Plane { point: Vec3(0.0, 0.0, 0.0), normal: Vec3(0.0, 1.0, 0.0), material: Box::new(Lambertian { albedo: Vec3(0.5, 0.5, 0.5) }) }),
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+    for (i, material) in materials.into_iter().enumerate() {
+        let center = if i < 4 { Vec3(-4.5 + 3.0 * i as f32, 1.0, -1.5) } else { Vec3(-3.0 + 3.0 * (i - 4) as f32, 1.0, 1.5) };
+        world.push(Box::new(Sphere { center, radius: 1.0, material }));
+    }
+
+    let lights: HittableList = vec![
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    Scene { world, lights, background: Background::Sky }
+}
+
+/// Every conductor preset, polished in the back row and rough in the front row.
+fn conductor_scene() -> Scene {
+    let light = Vec3(20.0, 20.0, 20.0);
+
+    let mut world: HittableList = vec![
+        Box::new(Plane { point: Vec3(0.0, 0.0, 0.0), normal: Vec3(0.0, 1.0, 0.0), material: Box::new(Lambertian { albedo: Vec3(0.5, 0.5, 0.5) }) }),
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+    for (i, (name, _, _)) in CONDUCTOR_PRESETS.iter().enumerate() {
+        for (j, roughness) in [0.0, 0.3].iter().enumerate() {
+            let material = Conductor::preset(name, solid(*roughness)).expect("unknown conductor preset");
+            let center = Vec3(-4.8 + 2.4 * i as f32, 1.0, -1.5 + 3.0 * j as f32);
+            world.push(Box::new(Sphere { center, radius: 1.0, material: Box::new(material) }));
+        }
+    }
+
+    let lights: HittableList = vec![
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    Scene { world, lights, background: Background::Sky }
+}
+
+/// Glass spheres in front of thin light bars: in spectral mode, each sphere
+/// splits the bars seen through it into rainbows.
+fn dispersion_scene() -> Scene {
+    let light = Vec3(4.0, 4.0, 4.0);
+    let bar = |x: f32| -> Box<dyn Hittable> {
+        Box::new(XyRect { x0: x, x1: x + 0.15, y0: 0.0, y1: 3.0, k: -3.0, material: Box::new(DiffuseLight { emit: light }) })
+    };
+
+    let mut world: HittableList = vec![
+        Box::new(XzRect { x0: -20.0, x1: 20.0, z0: -20.0, z1: 20.0, k: 0.0, material: Box::new(Lambertian { albedo: Vec3(0.5, 0.5, 0.5) }) }),
+        // Dense flint glass (SF11).
+        Box::new(Sphere { center: Vec3(-2.2, 1.0, 0.0), radius: 1.0, material: Box::new(Dielectric {
+            index_of_refraction: 1.78,
+            dispersion: Some(Dispersion::Sellmeier { b: [1.737_597, 0.313_747_35, 1.898_781], c: [0.013_188_707, 0.062_306_814, 155.236_3] }),
+            absorption: Vec3(0.0, 0.0, 0.0)
+        }) }),
+        // Diamond.
+        Box::new(Sphere { center: Vec3(0.0, 1.0, 0.0), radius: 1.0, material: Box::new(Dielectric {
+            index_of_refraction: 2.42,
+            dispersion: Some(Dispersion::Sellmeier { b: [0.3306, 4.3356, 0.0], c: [0.030_625, 0.011_236, 0.0] }),
+            absorption: Vec3(0.0, 0.0, 0.0)
+        }) }),
+        // A glass with exaggerated dispersion.
+        Box::new(Sphere { center: Vec3(2.2, 1.0, 0.0), radius: 1.0, material: Box::new(Dielectric {
+            index_of_refraction: 1.55,
+            dispersion: Some(Dispersion::Cauchy { a: 1.45, b: 0.03 }),
+            absorption: Vec3(0.0, 0.0, 0.0)
+        }) }),
+    ];
+    let mut lights: HittableList = vec![];
+    for i in 0..8 {
+        let x = -4.0 + i as f32;
+        world.push(bar(x));
+        lights.push(bar(x));
+    }
+
+    Scene { world, lights, background: Background::Solid(Vec3(0.0, 0.0, 0.0)) }
+}
+
+/// Thin films: a soap bubble with patches of two thicknesses, coated glass,
+/// and metals with oxide layers, polished and fuzzy.
+fn thin_film_scene() -> Scene {
+    let light = Vec3(20.0, 20.0, 20.0);
+    let clear = |index_of_refraction: f32| Dielectric { index_of_refraction, dispersion: None, absorption: Vec3(0.0, 0.0, 0.0) };
+    let bubble_thickness = Box::new(CheckerTexture { odd: solid(300.0), even: solid(550.0) });
+
+    let films: Vec<(Substrate, Box<dyn Texture>, f32)> = vec![
+        (Substrate::Dielectric(clear(1.0)), bubble_thickness, 1.33),
+        // A quarter wave anti-reflection coating of magnesium fluoride.
+        (Substrate::Dielectric(clear(1.5)), solid(100.0), 1.38),
+        (Substrate::Metal(Metal { albedo: Vec3(0.6, 0.6, 0.6), fuzz: 0.0 }), solid(250.0), 2.4),
+        (Substrate::Metal(Metal { albedo: Vec3(0.6, 0.6, 0.6), fuzz: 0.2 }), solid(400.0), 2.4),
+    ];
+
+    let mut world: HittableList = vec![
+        Box::new(Plane { point: Vec3(0.0, 0.0, 0.0), normal: Vec3(0.0, 1.0, 0.0), material: Box::new(Lambertian { albedo: Vec3(0.5, 0.5, 0.5) }) }),
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+    for (i, (substrate, thickness, index_of_refraction)) in films.into_iter().enumerate() {
+        let material = ThinFilm { substrate, thickness, index_of_refraction: solid(index_of_refraction) };
+        world.push(Box::new(Sphere { center: Vec3(-3.6 + 2.4 * i as f32, 1.0, 0.0), radius: 1.0, material: Box::new(material) }));
+    }
+
+    let lights: HittableList = vec![
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    Scene { world, lights, background: Background::Sky }
+}
+
+/// Combined materials: car paint with metallic flakes under a clear coat,
+/// varnished wood, gold inlaid in plaster by a texture mask, and lacquered
+/// rough gold.
+fn layered_scene() -> Scene {
+    let light = Vec3(20.0, 20.0, 20.0);
+    let mask = || -> Box<dyn Texture> { Box::new(CheckerTexture { odd: solid(0.0), even: solid(1.0) }) };
+    let gold = |roughness: f32| Conductor::preset("gold", solid(roughness)).expect("unknown conductor preset");
+
+    let car_paint = CoatedMaterial {
+        base: Box::new(MixMaterial {
+            a: Box::new(Lambertian { albedo: Vec3(0.6, 0.05, 0.05) }),
+            b: Box::new(Metal { albedo: Vec3(0.9, 0.3, 0.3), fuzz: 0.3 }),
+            weight: solid(0.3)
+        }),
+        index_of_refraction: 1.5
+    };
+    let varnished_wood = CoatedMaterial {
+        base: Box::new(MixMaterial {
+            a: Box::new(Lambertian { albedo: Vec3(0.45, 0.25, 0.1) }),
+            b: Box::new(Lambertian { albedo: Vec3(0.3, 0.15, 0.05) }),
+            weight: mask()
+        }),
+        index_of_refraction: 1.5
+    };
+    let inlaid = MixMaterial {
+        a: Box::new(Lambertian { albedo: Vec3(0.8, 0.8, 0.75) }),
+        b: Box::new(gold(0.1)),
+        weight: mask()
+    };
+    let lacquered_gold = CoatedMaterial { base: Box::new(gold(0.4)), index_of_refraction: 1.5 };
+    let materials: Vec<Box<dyn Material>> = vec![Box::new(car_paint), Box::new(varnished_wood), Box::new(inlaid), Box::new(lacquered_gold)];
+
+    let mut world: HittableList = vec![
+        Box::new(Plane { point: Vec3(0.0, 0.0, 0.0), normal: Vec3(0.0, 1.0, 0.0), material: Box::new(Lambertian { albedo: Vec3(0.5, 0.5, 0.5) }) }),
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+    for (i, material) in materials.into_iter().enumerate() {
+        world.push(Box::new(Sphere { center: Vec3(-3.6 + 2.4 * i as f32, 1.0, 0.0), radius: 1.0, material }));
+    }
+
+    let lights: HittableList = vec![
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    Scene { world, lights, background: Background::Sky }
+}
+
+/// Clay spheres lit from behind the camera: Lambertian on the left, then
+/// Oren–Nayar with growing roughness, flatter and flatter like the full moon.
+fn oren_nayar_scene() -> Scene {
+    let light = Vec3(40.0, 40.0, 40.0);
+    let clay = Vec3(0.7, 0.5, 0.4);
+
+    let materials: Vec<Box<dyn Material>> = vec![
+        Box::new(Lambertian { albedo: clay }),
+        Box::new(OrenNayar { albedo: clay, sigma: 0.3 }),
+        Box::new(OrenNayar { albedo: clay, sigma: 1.0 }),
+    ];
+
+    let mut world: HittableList = vec![
+        Box::new(Plane { point: Vec3(0.0, 0.0, 0.0), normal: Vec3(0.0, 1.0, 0.0), material: Box::new(OrenNayar { albedo: Vec3(0.5, 0.5, 0.5), sigma: 0.5 }) }),
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 16.0), radius: 2.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+    for (i, material) in materials.into_iter().enumerate() {
+        world.push(Box::new(Sphere { center: Vec3(-2.4 + 2.4 * i as f32, 1.0, 0.0), radius: 1.0, material }));
+    }
+
+    let lights: HittableList = vec![
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 16.0), radius: 2.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    Scene { world, lights, background: Background::Solid(Vec3(0.0, 0.0, 0.0)) }
+}
+
+/// Brushed aluminium: spheres brushed around their axis, across it, and not
+/// at all, on a plate brushed along X.
+fn anisotropic_scene() -> Scene {
+    let light = Vec3(20.0, 20.0, 20.0);
+    let aluminium = || -> Box<dyn Texture> { Box::new(SolidColor { color: Vec3(0.91, 0.92, 0.92) }) };
+
+    let mut world: HittableList = vec![
+        Box::new(Plane { point: Vec3(0.0, 0.0, 0.0), normal: Vec3(0.0, 1.0, 0.0), material: Box::new(Lambertian { albedo: Vec3(0.5, 0.5, 0.5) }) }),
+        Box::new(XzRect { x0: -4.0, x1: 4.0, z0: -2.0, z1: 2.0, k: 0.01, material: Box::new(AnisotropicConductor { albedo: aluminium(), alpha_x: 0.02, alpha_y: 0.3 }) }),
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+    for (i, (alpha_x, alpha_y)) in [(0.05, 0.4), (0.4, 0.05), (0.15, 0.15)].iter().enumerate() {
+        let material = AnisotropicConductor { albedo: aluminium(), alpha_x: *alpha_x, alpha_y: *alpha_y };
+        world.push(Box::new(Sphere { center: Vec3(-2.4 + 2.4 * i as f32, 1.01, 0.0), radius: 1.0, material: Box::new(material) }));
+    }
+
+    let lights: HittableList = vec![
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    Scene { world, lights, background: Background::Sky }
+}
+
+/// Translucent materials lit from behind: skin, marble and jade walked
+/// through with random-walk subsurface scattering, and a ball of fog.
+fn subsurface_scene() -> Scene {
+    let light = Vec3(6.0, 6.0, 6.0);
+    let skin = HomogeneousMedium::from_mean_free_path(Vec3(0.95, 0.8, 0.7), Vec3(0.5, 0.2, 0.1));
+    let marble = HomogeneousMedium::from_mean_free_path(Vec3(0.99, 0.99, 0.98), Vec3(0.15, 0.15, 0.15));
+    let jade = HomogeneousMedium::from_mean_free_path(Vec3(0.6, 0.95, 0.7), Vec3(0.3, 0.3, 0.3));
+    let fog = HomogeneousMedium::from_density(0.8, Vec3(0.9, 0.9, 0.9));
+
+    let materials: Vec<Box<dyn Material>> = vec![
+        Box::new(Subsurface { index_of_refraction: 1.4, medium: skin }),
+        Box::new(Subsurface { index_of_refraction: 1.5, medium: marble }),
+        Box::new(Subsurface { index_of_refraction: 1.6, medium: jade }),
+        Box::new(ConstantMedium { medium: fog }),
+    ];
+
+    let mut world: HittableList = vec![
+        Box::new(Plane { point: Vec3(0.0, 0.0, 0.0), normal: Vec3(0.0, 1.0, 0.0), material: Box::new(Lambertian { albedo: Vec3(0.5, 0.5, 0.5) }) }),
+        Box::new(Sphere { center: Vec3(0.0, 6.0, -6.0), radius: 2.5, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+    for (i, material) in materials.into_iter().enumerate() {
+        world.push(Box::new(Sphere { center: Vec3(-3.6 + 2.4 * i as f32, 1.0, 0.0), radius: 1.0, material }));
+    }
+
+    let lights: HittableList = vec![
+        Box::new(Sphere { center: Vec3(0.0, 6.0, -6.0), radius: 2.5, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    Scene { world, lights, background: Background::Solid(Vec3(0.1, 0.1, 0.12)) }
+}
+
+/// Perturbed normals: hammered chrome and rough stone bump mapped with
+/// noise, and gold and a steel plate with a normal map of rivets.
+fn bump_scene() -> Scene {
+    let light = Vec3(20.0, 20.0, 20.0);
+    let rivets = Rc::new(ImageTexture::load_ppm("assets/rivets.ppm").expect("could not read assets/rivets.ppm"));
+
+    let materials: Vec<Box<dyn Material>> = vec![
+        Box::new(BumpMap {
+            base: Box::new(Metal { albedo: Vec3(0.9, 0.9, 0.9), fuzz: 0.0 }),
+            height: Box::new(NoiseTexture { noise: Perlin::new(), scale: 2.0 }),
+            scale: 0.15
+        }),
+        Box::new(BumpMap {
+            base: Box::new(Lambertian { albedo: Vec3(0.6, 0.55, 0.5) }),
+            height: Box::new(NoiseTexture { noise: Perlin::new(), scale: 8.0 }),
+            scale: 0.05
+        }),
+        Box::new(NormalMap {
+            base: Box::new(RoughConductor { albedo: Box::new(SolidColor { color: Vec3(1.0, 0.78, 0.34) }), roughness: solid(0.2) }),
+            normal_map: Box::new(Rc::clone(&rivets))
+        }),
+    ];
+
+    let mut world: HittableList = vec![
+        Box::new(Plane { point: Vec3(0.0, 0.0, 0.0), normal: Vec3(0.0, 1.0, 0.0), material: Box::new(Lambertian { albedo: Vec3(0.5, 0.5, 0.5) }) }),
+        Box::new(XzRect { x0: -4.0, x1: 4.0, z0: -2.0, z1: 2.0, k: 0.01, material: Box::new(NormalMap {
+            base: Box::new(Metal { albedo: Vec3(0.8, 0.8, 0.8), fuzz: 0.05 }),
+            normal_map: Box::new(rivets)
+        }) }),
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+    for (i, material) in materials.into_iter().enumerate() {
+        world.push(Box::new(Sphere { center: Vec3(-2.4 + 2.4 * i as f32, 1.01, 0.0), radius: 1.0, material }));
+    }
+
+    let lights: HittableList = vec![
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    Scene { world, lights, background: Background::Sky }
+}
+
+/// Alpha masks: a fence cut out of a quad by a checker mask, casting a
+/// checkered shadow, and a sphere eaten away stochastically by noise.
+fn alpha_scene() -> Scene {
+    let light = Vec3(30.0, 30.0, 30.0);
+    let mask = Box::new(CheckerTexture { odd: solid(0.0), even: solid(1.0) });
+
+    let world: HittableList = vec![
+        Box::new(Plane { point: Vec3(0.0, 0.0, 0.0), normal: Vec3(0.0, 1.0, 0.0), material: Box::new(Lambertian { albedo: Vec3(0.5, 0.5, 0.5) }) }),
+        Box::new(AlphaMasked {
+            object: Box::new(XyRect { x0: -3.0, x1: 3.0, y0: 0.0, y1: 2.0, k: 1.5, material: Box::new(Lambertian { albedo: Vec3(0.55, 0.35, 0.2) }) }),
+            alpha: mask,
+            mode: AlphaMode::Cutout(0.5)
+        }),
+        Box::new(Sphere { center: Vec3(-1.2, 1.0, -1.0), radius: 1.0, material: Box::new(Lambertian { albedo: Vec3(0.2, 0.3, 0.7) }) }),
+        Box::new(AlphaMasked {
+            object: Box::new(Sphere { center: Vec3(1.2, 1.0, -1.0), radius: 1.0, material: Box::new(Lambertian { albedo: Vec3(0.8, 0.6, 0.2) }) }),
+            alpha: Box::new(ScaledTexture { texture: Box::new(NoiseTexture { noise: Perlin::new(), scale: 3.0 }), scale: Vec3(2.5, 2.5, 2.5) }),
+            mode: AlphaMode::Stochastic
+        }),
+        Box::new(Sphere { center: Vec3(2.0, 7.0, 8.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    let lights: HittableList = vec![
+        Box::new(Sphere { center: Vec3(2.0, 7.0, 8.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    Scene { world, lights, background: Background::Sky }
+}
+
+/// Analytic primitives on an infinite plane: a capped and an open tilted
+/// cylinder, a cone, a glass torus, and a checkered disk, lit by a disk light.
+fn primitives_scene() -> Scene {
+    let light = Vec3(4.0, 4.0, 4.0);
+    let up = Vec3(0.0, 1.0, 0.0);
+    let checker = MixMaterial {
+        a: Box::new(Lambertian { albedo: Vec3(0.2, 0.3, 0.1) }),
+        b: Box::new(Lambertian { albedo: Vec3(0.9, 0.9, 0.9) }),
+        weight: Box::new(CheckerTexture { odd: solid(0.0), even: solid(1.0) })
+    };
+
+    let world: HittableList = vec![
+        Box::new(Plane { point: Vec3(0.0, 0.0, 0.0), normal: up, material: Box::new(Lambertian { albedo: Vec3(0.5, 0.5, 0.5) }) }),
+        Box::new(Cylinder { center: Vec3(-3.6, 0.0, 0.0), axis: up, height: 2.0, radius: 0.8, capped: true, material: Box::new(Lambertian { albedo: Vec3(0.7, 0.2, 0.2) }) }),
+        Box::new(Cylinder { center: Vec3(-1.2, 0.0, 0.0), axis: Vec3(0.3, 1.0, 0.0).unit_vector(), height: 1.6, radius: 0.7, capped: false, material: Box::new(Metal { albedo: Vec3(0.8, 0.8, 0.8), fuzz: 0.1 }) }),
+        Box::new(Cone { center: Vec3(1.2, 0.0, 0.0), axis: up, height: 2.2, radius: 0.9, capped: true, material: Box::new(Lambertian { albedo: Vec3(0.2, 0.5, 0.8) }) }),
+        Box::new(Torus { center: Vec3(3.6, 0.9, 0.0), axis: Vec3(0.0, 1.0, 1.0).unit_vector(), major_radius: 0.7, minor_radius: 0.25, material: Box::new(Dielectric { index_of_refraction: 1.5, dispersion: None, absorption: Vec3(0.0, 0.0, 0.0) }) }),
+        Box::new(Disk { center: Vec3(0.0, 0.01, 2.5), normal: up, radius: 1.2, material: Box::new(checker) }),
+        Box::new(Disk { center: Vec3(0.0, 6.0, 4.0), normal: -up, radius: 2.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    let lights: HittableList = vec![
+        Box::new(Disk { center: Vec3(0.0, 6.0, 4.0), normal: -up, radius: 2.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    Scene { world, lights, background: Background::Sky }
+}
+
+/// Constructive solid geometry: a glass lens where two spheres intersect, a
+/// cube drilled through along each axis, a cube rounded by a sphere, and a
+/// sphere with a bite taken out of it.
+fn csg_scene() -> Scene {
+    let light = Vec3(30.0, 30.0, 30.0);
+    let glass = || Box::new(Dielectric { index_of_refraction: 1.5, dispersion: None, absorption: Vec3(0.0, 0.0, 0.0) });
+    let cube = |center: Vec3, half: f32, material: Box<dyn Material>| -> Box<dyn Hittable> {
+        Box::new(Cuboid { minimum: center - Vec3(half, half, half), maximum: center + Vec3(half, half, half), material })
+    };
+
+    // A biconvex lens, where two large spheres overlap.
+    let lens = Intersection {
+        a: Box::new(Sphere { center: Vec3(-3.6, 1.0, -1.6), radius: 2.0, material: glass() }),
+        b: Box::new(Sphere { center: Vec3(-3.6, 1.0, 1.6), radius: 2.0, material: glass() })
+    };
+
+    // A cube with holes drilled through along each axis, painted red inside.
+    let center = Vec3(-1.2, 1.0, 0.0);
+    let drill = |axis: Vec3| -> Box<dyn Hittable> {
+        Box::new(Cylinder { center: center - axis * 1.0, axis, height: 2.0, radius: 0.4, capped: true, material: Box::new(Lambertian { albedo: Vec3(0.7, 0.1, 0.1) }) })
+    };
+    let drilled = Difference {
+        a: cube(center, 0.7, Box::new(Lambertian { albedo: Vec3(0.8, 0.8, 0.8) })),
+        b: Box::new(Union {
+            a: drill(Vec3(1.0, 0.0, 0.0)),
+            b: Box::new(Union { a: drill(Vec3(0.0, 1.0, 0.0)), b: drill(Vec3(0.0, 0.0, 1.0)) })
+        })
+    };
+
+    // A cube with rounded corners.
+    let rounded = Intersection {
+        a: cube(Vec3(1.2, 1.0, 0.0), 0.75, Box::new(Metal { albedo: Vec3(0.8, 0.6, 0.2), fuzz: 0.2 })),
+        b: Box::new(Sphere { center: Vec3(1.2, 1.0, 0.0), radius: 1.0, material: Box::new(Metal { albedo: Vec3(0.8, 0.6, 0.2), fuzz: 0.2 }) })
+    };
+
+    // A sphere with a bite taken out of it.
+    let bitten = Difference {
+        a: Box::new(Sphere { center: Vec3(3.6, 1.0, 0.0), radius: 1.0, material: Box::new(Lambertian { albedo: Vec3(0.2, 0.5, 0.8) }) }),
+        b: Box::new(Sphere { center: Vec3(3.0, 1.6, 0.8), radius: 0.7, material: Box::new(Lambertian { albedo: Vec3(0.9, 0.9, 0.6) }) })
+    };
+
+    let world: HittableList = vec![
+        Box::new(Plane { point: Vec3(0.0, 0.0, 0.0), normal: Vec3(0.0, 1.0, 0.0), material: Box::new(Lambertian { albedo: Vec3(0.5, 0.5, 0.5) }) }),
+        Box::new(lens),
+        Box::new(drilled),
+        Box::new(rounded),
+        Box::new(bitten),
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    let lights: HittableList = vec![
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    Scene { world, lights, background: Background::Sky }
+}
+
+/// Signed distance fields: a glass rounded box, two spheres blended by a
+/// smooth union, a twisted golden bar, a Mandelbulb, and a repeated row of
+/// small spheres.
+fn sdf_scene() -> Scene {
+    let light = Vec3(30.0, 30.0, 30.0);
+    let around = |center: Vec3, radius: f32| Aabb::around(center, Vec3(radius, radius, radius));
+
+    let world: HittableList = vec![
+        Box::new(Plane { point: Vec3(0.0, 0.0, 0.0), normal: Vec3(0.0, 1.0, 0.0), material: Box::new(Lambertian { albedo: Vec3(0.5, 0.5, 0.5) }) }),
+        Box::new(SdfObject {
+            sdf: Box::new(RoundBox { center: Vec3(-3.6, 0.8, 0.0), half_size: Vec3(0.8, 0.8, 0.8), radius: 0.2 }),
+            bounds: around(Vec3(-3.6, 0.8, 0.0), 0.81),
+            material: Box::new(Dielectric { index_of_refraction: 1.5, dispersion: None, absorption: Vec3(0.0, 0.0, 0.0) })
+        }),
+        Box::new(SdfObject {
+            sdf: Box::new(SmoothUnion {
+                a: Box::new(SdfSphere { center: Vec3(-1.5, 0.8, 0.0), radius: 0.7 }),
+                b: Box::new(SdfSphere { center: Vec3(-0.9, 1.4, 0.2), radius: 0.5 }),
+                smoothness: 0.4
+            }),
+            bounds: around(Vec3(-1.2, 1.0, 0.0), 1.2),
+            material: Box::new(Lambertian { albedo: Vec3(0.2, 0.6, 0.3) })
+        }),
+        Box::new(SdfObject {
+            sdf: Box::new(Twist {
+                sdf: Box::new(RoundBox { center: Vec3(0.0, 1.0, 0.0), half_size: Vec3(0.4, 1.0, 0.4), radius: 0.05 }),
+                rate: 1.2,
+                radius: 0.6
+            }),
+            // Twisted around the world y axis, then moved into place.
+            bounds: Aabb { minimum: Vec3(-0.6, 0.0, -0.6), maximum: Vec3(0.6, 2.0, 0.6) },
+            material: Box::new(Metal { albedo: Vec3(0.8, 0.6, 0.2), fuzz: 0.1 })
+        }),
+        Box::new(SdfObject {
+            sdf: Box::new(Mandelbulb { center: Vec3(2.4, 1.0, 0.0), scale: 0.8, power: 8.0, iterations: 10 }),
+            bounds: around(Vec3(2.4, 1.0, 0.0), 1.0),
+            material: Box::new(Lambertian { albedo: Vec3(0.7, 0.3, 0.2) })
+        }),
+        Box::new(SdfObject {
+            sdf: Box::new(Repeat { sdf: Box::new(SdfSphere { center: Vec3(0.0, 0.0, 0.0), radius: 0.2 }), period: Vec3(0.6, 0.0, 0.6) }),
+            bounds: Aabb { minimum: Vec3(-6.0, 0.0, -4.5), maximum: Vec3(6.0, 0.2, -2.1) },
+            material: Box::new(Lambertian { albedo: Vec3(0.2, 0.3, 0.7) })
+        }),
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    let lights: HittableList = vec![
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    Scene { world, lights, background: Background::Sky }
+}
+
+/// Quadrics and an implicit surface: a glass ellipsoid, a mirror paraboloid
+/// dish and a hyperboloid tower, both clipped by boxes, and a tangle cube.
+fn quadric_scene() -> Scene {
+    let light = Vec3(30.0, 30.0, 30.0);
+
+    let dish = Quadric {
+        clip: Some(Aabb { minimum: Vec3(-2.4, 0.2, -1.2), maximum: Vec3(0.0, 1.4, 1.2) }),
+        ..Quadric::paraboloid(Vec3(-1.2, 0.2, 0.0), 0.3, Box::new(Metal { albedo: Vec3(0.9, 0.9, 0.9), fuzz: 0.0 }))
+    };
+    let tower = Quadric {
+        clip: Some(Aabb { minimum: Vec3(0.4, 0.0, -0.8), maximum: Vec3(2.0, 2.0, 0.8) }),
+        ..Quadric::hyperboloid(Vec3(1.2, 1.0, 0.0), 0.45, 0.8, Box::new(Lambertian { albedo: Vec3(0.7, 0.6, 0.5) }))
+    };
+    // The tangle cube, x⁴ - 5x² + y⁴ - 5y² + z⁴ - 5z² + 11.8 = 0. Within
+    // its bounds, |x| <= 3, so each term changes by at most 4·27 - 10·3 = 78
+    // per unit of x, and the function by at most √3 · 78 · 2.5 < 340 per unit.
+    let center = Vec3(3.6, 1.1, 0.0);
+    let tangle = ImplicitSurface {
+        function: Box::new(move |p| {
+            let q = (p - center) * 2.5;
+            let f = |x: f32| x * x * x * x - 5.0 * x * x;
+            f(q.x()) + f(q.y()) + f(q.z()) + 11.8
+        }),
+        bounds: Aabb::around(center, Vec3(1.2, 1.2, 1.2)),
+        lipschitz: 340.0,
+        material: Box::new(Lambertian { albedo: Vec3(0.2, 0.5, 0.8) })
+    };
+
+    let world: HittableList = vec![
+        Box::new(Plane { point: Vec3(0.0, 0.0, 0.0), normal: Vec3(0.0, 1.0, 0.0), material: Box::new(Lambertian { albedo: Vec3(0.5, 0.5, 0.5) }) }),
+        Box::new(Quadric::ellipsoid(Vec3(-3.6, 1.0, 0.0), Vec3(0.8, 1.0, 0.6), Box::new(Dielectric { index_of_refraction: 1.5, dispersion: None, absorption: Vec3(0.0, 0.0, 0.0) }))),
+        Box::new(dish),
+        Box::new(tower),
+        Box::new(tangle),
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    let lights: HittableList = vec![
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    Scene { world, lights, background: Background::Sky }
+}
+
+/// Rolling hills from a grayscale heightmap, textured with noise, in a thin
+/// fog. The fog box encloses the whole terrain, so that its walls never meet
+/// the ground.
+fn terrain_scene() -> Scene {
+    let light = Vec3(15.0, 14.0, 12.0);
+    let heightmap = ImageTexture::load_ppm("assets/terrain.ppm").expect("could not read assets/terrain.ppm");
+    let ground = Principled::new(Box::new(ScaledTexture {
+        texture: Box::new(NoiseTexture { noise: Perlin::new(), scale: 1.5 }),
+        scale: Vec3(0.45, 0.4, 0.25)
+    }));
+    let fog = HomogeneousMedium::from_density(0.04, Vec3(0.9, 0.9, 0.9));
+
+    let world: HittableList = vec![
+        Box::new(Plane { point: Vec3(0.0, 0.0, 0.0), normal: Vec3(0.0, 1.0, 0.0), material: Box::new(Lambertian { albedo: Vec3(0.3, 0.35, 0.25) }) }),
+        Box::new(Heightfield::from_image(&heightmap, Vec3(-8.0, 0.0, -8.0), Vec3(16.0, 3.0, 16.0), Box::new(ground))),
+        Box::new(Cuboid { minimum: Vec3(-9.0, -1.0, -9.0), maximum: Vec3(9.0, 4.0, 9.0), material: Box::new(ConstantMedium { medium: fog }) }),
+        Box::new(Sphere { center: Vec3(10.0, 12.0, -6.0), radius: 2.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    let lights: HittableList = vec![
+        Box::new(Sphere { center: Vec3(10.0, 12.0, -6.0), radius: 2.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    Scene { world, lights, background: Background::Sky }
+}
+
+/// Locks of blond, brown, black and red hair hanging over a tuft of grass.
+fn hair_scene() -> Scene {
+    let mut rng = rand::thread_rng();
+    let light = Vec3(30.0, 30.0, 30.0);
+    let hairs: Vec<Box<dyn Fn() -> Hair>> = vec![
+        Box::new(|| Hair::from_melanin(0.3, 0.0)),
+        Box::new(|| Hair::from_melanin(1.3, 0.0)),
+        Box::new(|| Hair::from_melanin(8.0, 0.0)),
+        Box::new(|| Hair::from_color(Vec3(0.7, 0.25, 0.1), 0.3)),
+    ];
+
+    // Hundreds of curves, kept in a BVH so that rays only test the few they
+    // pass near.
+    let mut curves: Vec<Box<dyn Hittable>> = Vec::new();
+    for (i, hair) in hairs.iter().enumerate() {
+        let top = Vec3(-3.6 + 2.4 * i as f32, 2.6, 0.0);
+        for _ in 0..120 {
+            let offset = Vec3(rng.gen_range(-0.3..0.3), 0.0, rng.gen_range(-0.15..0.15));
+            let sway = |height: f32| Vec3(0.15 * f32::sin(3.0 * height + offset.x() * 10.0), 0.0, 0.1 * offset.z());
+            let control_points = [0.0, 0.8, 1.6, 2.4].map(|drop| top + offset * (1.0 + 0.3 * drop) + sway(drop) - Vec3(0.0, drop, 0.0));
+            curves.push(Box::new(Curve { control_points, widths: [0.012, 0.006], kind: CurveType::Cylinder, material: Box::new(hair()) }));
+        }
+    }
+    for _ in 0..200 {
+        let root = Vec3(rng.gen_range(-1.5..1.5), 0.0, rng.gen_range(1.2..2.2));
+        let lean = Vec3(rng.gen_range(-0.3..0.3), 0.0, rng.gen_range(-0.1..0.3));
+        let height = rng.gen_range(0.3..0.7);
+        let control_points = [0.0, 0.33, 0.67, 1.0].map(|k: f32| root + lean * (k * k) + Vec3(0.0, height * k, 0.0));
+        let green = Vec3(0.15, rng.gen_range(0.35..0.55), 0.08);
+        curves.push(Box::new(Curve { control_points, widths: [0.04, 0.002], kind: CurveType::Flat, material: Box::new(Lambertian { albedo: green }) }));
+    }
+
+    let world: HittableList = vec![
+        Box::new(Plane { point: Vec3(0.0, 0.0, 0.0), normal: Vec3(0.0, 1.0, 0.0), material: Box::new(Lambertian { albedo: Vec3(0.5, 0.5, 0.5) }) }),
+        Box::new(Bvh::new(curves, 0.0, 1.0)),
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    let lights: HittableList = vec![
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    Scene { world, lights, background: Background::Sky }
+}
+
+/// Catmull–Clark subdivision of control cages read from OBJ files: a smooth
+/// cube, a cube with sharp and semi-sharp creases, an open box with
+/// boundaries, and a ring refined to a level picked from its size on screen.
+fn subdivision_scene(camera: &Camera, image_width: usize) -> Scene {
+    let light = Vec3(30.0, 30.0, 30.0);
+    let color = |r: f32, g: f32, b: f32| -> Box<dyn Texture> { Box::new(SolidColor { color: Vec3(r, g, b) }) };
+    let cube = PolygonMesh::load_obj("assets/cube.obj").expect("could not read assets/cube.obj");
+    let ring = PolygonMesh::load_obj("assets/ring.obj").expect("could not read assets/ring.obj");
+    let place = |mesh: &PolygonMesh, offset: Vec3| PolygonMesh { positions: mesh.positions.iter().map(|&p| p + offset).collect(), ..mesh.clone() };
+
+    let mut plastic = Principled::new(color(0.8, 0.1, 0.1));
+    plastic.roughness = solid(0.3);
+    let mut gold = Principled::new(color(1.0, 0.78, 0.34));
+    gold.metallic = solid(1.0);
+    gold.roughness = solid(0.3);
+    let mut car_paint = Principled::new(color(0.05, 0.1, 0.5));
+    car_paint.roughness = solid(0.6);
+    car_paint.clearcoat = solid(1.0);
+
+    // The top of the creased cube is sharp, and its sides are rounded after
+    // two levels.
+    let mut creased = place(&cube, Vec3(-1.2, 0.0, 0.0));
+    for edge in [(4, 5), (5, 6), (6, 7), (4, 7)] {
+        creased.creases.insert(edge, f32::INFINITY);
+    }
+    for edge in [(0, 4), (1, 5), (2, 6), (3, 7)] {
+        creased.creases.insert(edge, 2.0);
+    }
+    let mut open_box = place(&cube, Vec3(1.2, 0.0, 0.0));
+    open_box.faces.remove(1);
+    let ring = place(&ring, Vec3(3.6, 0.0, 0.0));
+    let ring_level = subdivision::screen_space_level(&ring, camera, image_width, 4.0, 5);
+
+    let world: HittableList = vec![
+        Box::new(Plane { point: Vec3(0.0, 0.0, 0.0), normal: Vec3(0.0, 1.0, 0.0), material: Box::new(Lambertian { albedo: Vec3(0.5, 0.5, 0.5) }) }),
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+        Box::new(TriangleMesh::from_polygons(&subdivision::subdivide(&place(&cube, Vec3(-3.6, 0.0, 0.0)), 4), Box::new(plastic))),
+        Box::new(TriangleMesh::from_polygons(&subdivision::subdivide(&creased, 4), Box::new(gold))),
+        Box::new(TriangleMesh::from_polygons(&subdivision::subdivide(&open_box, 4), Box::new(Lambertian { albedo: Vec3(0.7, 0.7, 0.7) }))),
+        Box::new(TriangleMesh::from_polygons(&subdivision::subdivide(&ring, ring_level), Box::new(car_paint))),
+    ];
+
+    let lights: HittableList = vec![
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    Scene { world, lights, background: Background::Sky }
+}
+
+/// Displacement mapping of tessellated meshes: a rock displaced by noise, a
+/// planet displaced by the terrain heightmap, and a ring with studs of a
+/// checker pattern.
+fn displacement_scene() -> Scene {
+    let light = Vec3(30.0, 30.0, 30.0);
+    let cube = PolygonMesh::load_obj("assets/cube.obj").expect("could not read assets/cube.obj");
+    let ring = PolygonMesh::load_obj("assets/ring.obj").expect("could not read assets/ring.obj");
+    let heightmap = ImageTexture::load_ppm("assets/terrain.ppm").expect("could not read assets/terrain.ppm");
+    let place = |mesh: &PolygonMesh, offset: Vec3| PolygonMesh { positions: mesh.positions.iter().map(|&p| p + offset).collect(), ..mesh.clone() };
+
+    let rock = Displacement { height: Box::new(NoiseTexture { noise: Perlin::new(), scale: 2.5 }), scale: 0.35 };
+    let planet = Displacement { height: Box::new(heightmap), scale: 0.5 };
+    let studs = Displacement {
+        height: Box::new(CheckerTexture { odd: solid(0.0), even: solid(1.0) }),
+        scale: 0.06
+    };
+    let mut ocean = Principled::new(Box::new(SolidColor { color: Vec3(0.35, 0.45, 0.3) }));
+    ocean.roughness = solid(0.7);
+
+    let world: HittableList = vec![
+        Box::new(Plane { point: Vec3(0.0, 0.0, 0.0), normal: Vec3(0.0, 1.0, 0.0), material: Box::new(Lambertian { albedo: Vec3(0.5, 0.5, 0.5) }) }),
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+        Box::new(TriangleMesh::from_polygons(
+            &rock.apply(&subdivision::tessellate(&subdivision::subdivide(&place(&cube, Vec3(-2.6, 0.0, 0.0)), 2), 3)),
+            Box::new(Lambertian { albedo: Vec3(0.45, 0.4, 0.35) })
+        )),
+        Box::new(TriangleMesh::from_polygons(
+            &planet.apply(&subdivision::tessellate(&subdivision::subdivide(&place(&cube, Vec3(0.0, 0.2, 0.0)), 3), 3)),
+            Box::new(ocean)
+        )),
+        Box::new(TriangleMesh::from_polygons(
+            &studs.apply(&subdivision::tessellate(&subdivision::subdivide(&place(&ring, Vec3(2.6, 0.0, 0.0)), 2), 3)),
+            Box::new(Metal { albedo: Vec3(0.8, 0.6, 0.4), fuzz: 0.2 })
+        )),
+    ];
+
+    let lights: HittableList = vec![
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    Scene { world, lights, background: Background::Sky }
+}
+
+/// Metaballs: a drop of water merging with the floor of a puddle, two blobs
+/// of mercury pulling apart across the shutter, and a splash of small drops.
+fn metaballs_scene() -> Scene {
+    let mut rng = rand::thread_rng();
+    let light = Vec3(30.0, 30.0, 30.0);
+
+    let water = vec![
+        Ball::new(Vec3(-2.6, 1.2, 0.0), 1.0, 1.0),
+        Ball::new(Vec3(-2.6, 0.2, 0.0), 1.2, 1.0),
+        Ball::new(Vec3(-3.4, 0.1, 0.3), 1.0, 1.0),
+        Ball::new(Vec3(-1.8, 0.1, -0.2), 1.0, 1.0),
+    ];
+    let mercury = vec![
+        Ball { center_0: Vec3(-0.25, 0.9, 0.0), center_1: Vec3(-1.1, 0.9, 0.0), time_0: 0.0, time_1: 1.0, radius: 1.1, weight: 1.0 },
+        Ball { center_0: Vec3(0.25, 0.9, 0.0), center_1: Vec3(1.1, 0.9, 0.0), time_0: 0.0, time_1: 1.0, radius: 1.1, weight: 1.0 },
+    ];
+    let splash = (0..40).map(|_| {
+        let angle = rng.gen_range(0.0..2.0 * std::f32::consts::PI);
+        let distance = rng.gen_range(0.0..0.9);
+        let height = rng.gen_range(0.2..1.8) * (1.0 - distance);
+        Ball::new(Vec3(2.6 + distance * angle.cos(), height, distance * angle.sin()), rng.gen_range(0.25..0.45), 1.0)
+    }).collect();
+
+    let mut red = Principled::new(Box::new(SolidColor { color: Vec3(0.7, 0.05, 0.05) }));
+    red.roughness = solid(0.2);
+    red.clearcoat = solid(1.0);
+
+    let world: HittableList = vec![
+        Box::new(Plane { point: Vec3(0.0, 0.0, 0.0), normal: Vec3(0.0, 1.0, 0.0), material: Box::new(Lambertian { albedo: Vec3(0.5, 0.5, 0.5) }) }),
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+        Box::new(Metaballs {
+            balls: water,
+            threshold: 0.3,
+            material: Box::new(Dielectric { index_of_refraction: 1.33, dispersion: None, absorption: Vec3(0.3, 0.08, 0.02) })
+        }),
+        Box::new(Metaballs { balls: mercury, threshold: 0.3, material: Box::new(Metal { albedo: Vec3(0.8, 0.8, 0.82), fuzz: 0.0 }) }),
+        Box::new(Metaballs { balls: splash, threshold: 0.3, material: Box::new(red) }),
+    ];
+
+    let lights: HittableList = vec![
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    Scene { world, lights, background: Background::Sky }
+}
+
+/// Deformation motion blur: a jelly cube squashing, a flag waving, and a bar
+/// twisting during the shutter, with three, four and five frames.
+fn deformation_scene() -> Scene {
+    let light = Vec3(30.0, 30.0, 30.0);
+    let cube = PolygonMesh::load_obj("assets/cube.obj").expect("could not read assets/cube.obj");
+    let transform = |mesh: &PolygonMesh, f: &dyn Fn(Vec3) -> Vec3| PolygonMesh { positions: mesh.positions.iter().map(|&p| f(p)).collect(), ..mesh.clone() };
+
+    // The jelly keeps its volume as it squashes.
+    let jelly = subdivision::subdivide(&cube, 3);
+    let jelly_frames: Vec<PolygonMesh> = [1.0, 0.75, 0.55].iter().map(|&squash: &f32| {
+        let widen = 1.0 / squash.sqrt();
+        transform(&jelly, &|p| Vec3(-2.6 + p.x() * widen, p.y() * squash, p.z() * widen))
+    }).collect();
+
+    // A grid of quads, hanging from a pole, with waves growing away from it.
+    let (columns, rows) = (24, 12);
+    let mut flag = PolygonMesh::default();
+    for j in 0..=rows {
+        for i in 0..=columns {
+            flag.positions.push(Vec3(-1.0 + 2.2 * i as f32 / columns as f32, 1.6 + 1.3 * j as f32 / rows as f32, 0.0));
+        }
+    }
+    for j in 0..rows {
+        for i in 0..columns {
+            let v = |i: usize, j: usize| j * (columns + 1) + i;
+            flag.faces.push(vec![v(i, j), v(i + 1, j), v(i + 1, j + 1), v(i, j + 1)]);
+        }
+    }
+    let flag_frames: Vec<PolygonMesh> = (0..4).map(|frame| {
+        let time = frame as f32 / 3.0;
+        transform(&flag, &|p| {
+            let along = p.x() + 1.0;
+            Vec3(p.x(), p.y(), 0.2 * along * f32::sin(4.0 * along - 1.5 * time + 0.5 * p.y()))
+        })
+    }).collect();
+
+    // A bar turning faster towards its top.
+    let bar = subdivision::tessellate(&transform(&cube, &|p| Vec3(p.x() * 0.375, p.y() * 1.25, p.z() * 0.375)), 3);
+    let bar_frames: Vec<PolygonMesh> = (0..5).map(|frame| {
+        let time = frame as f32 / 4.0;
+        transform(&bar, &|p| {
+            let (sin, cos) = f32::sin_cos(1.0 * time * p.y());
+            Vec3(2.6 + p.x() * cos - p.z() * sin, p.y(), p.x() * sin + p.z() * cos)
+        })
+    }).collect();
+
+    let mut jelly_material = Principled::new(Box::new(SolidColor { color: Vec3(0.2, 0.7, 0.2) }));
+    jelly_material.roughness = solid(0.2);
+    jelly_material.clearcoat = solid(1.0);
+    let mut gold = Principled::new(Box::new(SolidColor { color: Vec3(1.0, 0.78, 0.34) }));
+    gold.metallic = solid(1.0);
+    gold.roughness = solid(0.3);
+
+    let world: HittableList = vec![
+        Box::new(Plane { point: Vec3(0.0, 0.0, 0.0), normal: Vec3(0.0, 1.0, 0.0), material: Box::new(Lambertian { albedo: Vec3(0.5, 0.5, 0.5) }) }),
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+        Box::new(TriangleMesh::deforming(&jelly_frames, 0.0, 1.0, Box::new(jelly_material))),
+        Box::new(TriangleMesh::deforming(&flag_frames, 0.0, 1.0, Box::new(Lambertian { albedo: Vec3(0.7, 0.1, 0.1) }))),
+        Box::new(Cylinder { center: Vec3(-1.05, 0.0, 0.0), axis: Vec3(0.0, 1.0, 0.0), height: 3.0, radius: 0.05, capped: true, material: Box::new(Metal { albedo: Vec3(0.8, 0.8, 0.8), fuzz: 0.3 }) }),
+        Box::new(TriangleMesh::deforming(&bar_frames, 0.0, 1.0, Box::new(gold))),
+    ];
+
+    let lights: HittableList = vec![
+        Box::new(Sphere { center: Vec3(0.0, 6.0, 4.0), radius: 1.0, material: Box::new(DiffuseLight { emit: light }) }),
+    ];
+
+    Scene { world, lights, background: Background::Sky }
+}
+
+/// Spheres of diffuse, metal and glass under an environment map with a small
+/// bright sun, which is the only light, found by importance sampling.
+fn environment_scene() -> Scene {
+    let sky = ImageTexture::load_ppm("assets/sky.ppm").expect("could not read assets/sky.ppm");
+    let world: HittableList = vec![
+        Box::new(Plane { point: Vec3(0.0, 0.0, 0.0), normal: Vec3(0.0, 1.0, 0.0), material: Box::new(Lambertian { albedo: Vec3(0.5, 0.5, 0.5) }) }),
+        Box::new(Sphere { center: Vec3(-2.4, 1.0, 0.0), radius: 1.0, material: Box::new(Lambertian { albedo: Vec3(0.7, 0.3, 0.2) }) }),
+        Box::new(Sphere { center: Vec3(0.0, 1.0, 0.0), radius: 1.0, material: Box::new(Metal { albedo: Vec3(0.9, 0.9, 0.9), fuzz: 0.05 }) }),
+        Box::new(Sphere { center: Vec3(2.4, 1.0, 0.0), radius: 1.0, material: Box::new(Dielectric { index_of_refraction: 1.5, dispersion: None, absorption: Vec3(0.0, 0.0, 0.0) }) }),
+    ];
+
+    Scene { world, lights: vec![], background: Background::Environment(EnvironmentMap::new(sky, 16.0)) }
 }
 
 fn main() {
-    // Image
-    let aspect_ratio: f32 = 16.0 / 9.0;
+    // Scene, picked by the first command line argument.
+    let scene_number: u32 = std::env::args().nth(1).and_then(|arg| arg.parse().ok()).unwrap_or(1);
+    // Spectral rendering, when the second argument is "spectral".
+    let spectral = std::env::args().nth(2).is_some_and(|arg| arg == "spectral");
 
     let image_width = 400;
-    let image_height = (image_width as f32 / aspect_ratio) as i32;
-    // Camera
-    let lookfrom = Vec3(13.0, 2.0, 3.0);
-    let lookat = Vec3(0.0, 0.0, 0.0);
+    let aspect_ratio: f32;
+    let lookfrom: Vec3;
+    let lookat: Vec3;
+    let vfov: f32;
+    let aperture: f32;
     let vup = Vec3(0.0, 1.0, 0.0);
     let dist_to_focus = 10.0;
-    let aperture = 0.1;
-
     let time_0 = 0.0;
     let time_1 = 1.0;
 
-    let cam = Camera::new(lookfrom, lookat, vup, 20.0, aspect_ratio, aperture, dist_to_focus, time_0, time_1);
+    // World
+    let scene = match scene_number {
+        24 => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(0.0, 2.0, 10.0);
+            lookat = Vec3(0.0, 1.0, 0.0);
+            vfov = 30.0;
+            aperture = 0.0;
+            environment_scene()
+        }
+        23 => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(0.0, 3.0, 10.0);
+            lookat = Vec3(0.0, 1.2, 0.0);
+            vfov = 30.0;
+            aperture = 0.0;
+            deformation_scene()
+        }
+        22 => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(0.0, 3.0, 10.0);
+            lookat = Vec3(0.0, 0.8, 0.0);
+            vfov = 30.0;
+            aperture = 0.0;
+            metaballs_scene()
+        }
+        21 => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(0.0, 3.0, 10.0);
+            lookat = Vec3(0.0, 0.8, 0.0);
+            vfov = 30.0;
+            aperture = 0.0;
+            displacement_scene()
+        }
+        20 => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(0.0, 3.0, 10.0);
+            lookat = Vec3(0.0, 0.8, 0.0);
+            vfov = 35.0;
+            aperture = 0.0;
+            // Adaptive subdivision needs the camera before the scene.
+            let camera = Camera::new(lookfrom, lookat, vup, vfov, aspect_ratio, aperture, dist_to_focus, time_0, time_1);
+            subdivision_scene(&camera, image_width)
+        }
+        19 => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(0.0, 2.5, 10.0);
+            lookat = Vec3(0.0, 1.2, 0.0);
+            vfov = 35.0;
+            aperture = 0.0;
+            hair_scene()
+        }
+        18 => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(0.0, 9.0, 20.0);
+            lookat = Vec3(0.0, 0.5, 0.0);
+            vfov = 40.0;
+            aperture = 0.0;
+            terrain_scene()
+        }
+        17 => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(0.0, 4.0, 10.0);
+            lookat = Vec3(0.0, 1.0, 0.0);
+            vfov = 35.0;
+            aperture = 0.0;
+            quadric_scene()
+        }
+        16 => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(0.0, 4.0, 10.0);
+            lookat = Vec3(0.0, 1.0, 0.0);
+            vfov = 35.0;
+            aperture = 0.0;
+            sdf_scene()
+        }
+        15 => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(2.0, 4.0, 10.0);
+            lookat = Vec3(0.0, 1.0, 0.0);
+            vfov = 35.0;
+            aperture = 0.0;
+            csg_scene()
+        }
+        14 => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(0.0, 4.0, 12.0);
+            lookat = Vec3(0.0, 1.0, 0.0);
+            vfov = 35.0;
+            aperture = 0.0;
+            primitives_scene()
+        }
+        13 => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(0.0, 3.0, 10.0);
+            lookat = Vec3(0.0, 1.0, 0.0);
+            vfov = 35.0;
+            aperture = 0.0;
+            alpha_scene()
+        }
+        12 => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(0.0, 4.0, 12.0);
+            lookat = Vec3(0.0, 1.0, 0.0);
+            vfov = 30.0;
+            aperture = 0.0;
+            bump_scene()
+        }
+        11 => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(0.0, 4.0, 12.0);
+            lookat = Vec3(0.0, 1.0, 0.0);
+            vfov = 35.0;
+            aperture = 0.0;
+            subsurface_scene()
+        }
+        10 => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(0.0, 4.0, 12.0);
+            lookat = Vec3(0.0, 1.0, 0.0);
+            vfov = 30.0;
+            aperture = 0.0;
+            anisotropic_scene()
+        }
+        9 => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(0.0, 4.0, 12.0);
+            lookat = Vec3(0.0, 1.0, 0.0);
+            vfov = 30.0;
+            aperture = 0.0;
+            oren_nayar_scene()
+        }
+        8 => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(0.0, 4.0, 12.0);
+            lookat = Vec3(0.0, 1.0, 0.0);
+            vfov = 35.0;
+            aperture = 0.0;
+            layered_scene()
+        }
+        7 => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(0.0, 4.0, 12.0);
+            lookat = Vec3(0.0, 1.0, 0.0);
+            vfov = 35.0;
+            aperture = 0.0;
+            thin_film_scene()
+        }
+        6 => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(0.0, 1.5, 7.0);
+            lookat = Vec3(0.0, 1.0, 0.0);
+            vfov = 35.0;
+            aperture = 0.0;
+            dispersion_scene()
+        }
+        5 => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(0.0, 6.0, 14.0);
+            lookat = Vec3(0.0, 1.0, 0.0);
+            vfov = 40.0;
+            aperture = 0.0;
+            conductor_scene()
+        }
+        4 => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(0.0, 6.0, 14.0);
+            lookat = Vec3(0.0, 1.0, 0.0);
+            vfov = 40.0;
+            aperture = 0.0;
+            principled_scene()
+        }
+        3 => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(0.0, 6.0, 14.0);
+            lookat = Vec3(0.0, 1.0, 0.0);
+            vfov = 40.0;
+            aperture = 0.0;
+            microfacet_scene()
+        }
+        2 => {
+            aspect_ratio = 1.0;
+            lookfrom = Vec3(278.0, 278.0, -800.0);
+            lookat = Vec3(278.0, 278.0, 0.0);
+            vfov = 40.0;
+            aperture = 0.0;
+            cornell_box()
+        }
+        _ => {
+            aspect_ratio = 16.0 / 9.0;
+            lookfrom = Vec3(13.0, 2.0, 3.0);
+            lookat = Vec3(0.0, 0.0, 0.0);
+            vfov = 20.0;
+            aperture = 0.1;
+            hittable_world_random_scene()
+        }
+    };
+
+    // Image
+    let image_height = (image_width as f32 / aspect_ratio) as i32;
+    // Camera
+    let cam = Camera::new(lookfrom, lookat, vup, vfov, aspect_ratio, aperture, dist_to_focus, time_0, time_1);
     let samples_per_pixel = 100;
-     // Limit the number of child rays
+     // Limit the number of bounces
     let max_depth = 50;
-
-
-    // World
-    let world: Vec<Box<dyn Hittable>> = hittable_world_random_scene();
+    // Bounces before paths may be terminated by Russian roulette
+    let roulette_depth = 3;
 
     println!("P3\n{} {}\n{}", image_width, image_height, 255);
     for j in (0..image_height).rev() {
         eprintln!("Scanlines remaining: {}", j);
         for i in 0..image_width {
+            // RGB, or CIE XYZ in spectral mode.
             let mut pixel_color = Vec3(0.0, 0.0, 0.0);
-            for _s in 0..samples_per_pixel {
+            for s in 0..samples_per_pixel {
                 let u: f32 = (i as f32 + random::<f32>()) / (image_width - 1) as f32;
                 let v: f32 = (j as f32 + random::<f32>()) / (image_height - 1) as f32;
 
                 let r = cam.get_ray(u, v);
-                pixel_color = pixel_color + ray_color(r, &world, max_depth);
+                if spectral {
+                    // Wavelengths are stratified over the samples of the pixel.
+                    let wavelength = spectrum::sample_wavelength((s as f32 + random::<f32>()) / samples_per_pixel as f32);
+                    let r = Ray(r.origin(), r.direction(), r.time(), wavelength);
+                    let radiance = ray_color(r, &scene, max_depth, roulette_depth).x();
+                    pixel_color = pixel_color + spectrum::to_xyz(radiance, wavelength);
+                } else {
+                    pixel_color = pixel_color + ray_color(r, &scene, max_depth, roulette_depth);
+                }
+            }
+            if spectral {
+                pixel_color = spectrum::xyz_to_rgb(pixel_color);
             }
             write_color(pixel_color, samples_per_pixel);
        }
@@ -1,15 +1,37 @@
 use crate::ray::Ray;
 use crate::hit::HitRecord;
-use crate::vec3::{self, Vec3, random_in_unit_sphere};
+use crate::vec3::{Vec3, Color};
+use crate::pdf::{CosinePdf, FuzzyReflectionPdf};
+use crate::bsdf::{Bsdf, SampledBsdf};
+use crate::medium::HomogeneousMedium;
 use rand::random;
 
-pub struct Scatter {
-    pub r: Ray,
-    pub attenuation: Vec3
+pub enum Scatter {
+    /// A specular bounce: `r` is followed as is, there is nothing to sample.
+    Specular { r: Ray, attenuation: Vec3 },
+    /// A bounce with a distribution: the next direction is drawn from the BSDF,
+    /// which can also be evaluated for directions picked by light sampling.
+    Bsdf(Box<dyn Bsdf>)
 }
 
 pub trait Material {
     fn scatter(&self, r_in: Ray, record: HitRecord) -> Option<Scatter>;
+
+    fn emitted(&self, _r_in: Ray, _record: HitRecord) -> Color {
+        Vec3(0.0, 0.0, 0.0)
+    }
+
+    /// The medium filling the inside of objects made of this material, that
+    /// rays entering them travel through.
+    fn medium(&self) -> Option<HomogeneousMedium> {
+        None
+    }
+
+    /// Whether surfaces of this material only bound a medium: rays cross them
+    /// unchanged, and light sampling sees through them.
+    fn is_interface(&self) -> bool {
+        false
+    }
 }
 
 pub struct Lambertian {
@@ -22,10 +44,57 @@ pub struct Metal {
 }
 
 pub struct Dielectric {
-    pub index_of_refraction: f32
+    pub index_of_refraction: f32,
+    /// How the index of refraction varies with the wavelength, in spectral
+    /// mode. `index_of_refraction` is used otherwise.
+    pub dispersion: Option<Dispersion>,
+    /// Absorption coefficient per unit length inside the glass, for each
+    /// channel. Black gives clear glass.
+    pub absorption: Color
+}
+
+/// Models of the index of refraction of a dielectric as a function of the
+/// wavelength λ, in micrometers.
+pub enum Dispersion {
+    /// n(λ) = a + b / λ²
+    Cauchy { a: f32, b: f32 },
+    /// n(λ)² = 1 + Σ b_i λ² / (λ² - c_i)
+    Sellmeier { b: [f32; 3], c: [f32; 3] }
+}
+
+impl Dispersion {
+    pub fn index_of_refraction(&self, wavelength: f32) -> f32 {
+        let lambda2 = (wavelength / 1000.0).powi(2);
+        match self {
+            Dispersion::Cauchy { a, b } => a + b / lambda2,
+            Dispersion::Sellmeier { b, c } => {
+                let n2 = 1.0 + (0..3).map(|i| b[i] * lambda2 / (lambda2 - c[i])).sum::<f32>();
+                n2.sqrt()
+            }
+        }
+    }
+}
+
+/// An emissive material. It does not scatter, and emits `emit` from both faces.
+pub struct DiffuseLight {
+    pub emit: Color
 }
 
 impl Dielectric {
+    /// The absorption coefficient of a glass that lets through `transmittance`
+    /// of the light after traveling `distance` inside it.
+    pub fn absorption_from_transmittance(transmittance: Color, distance: f32) -> Color {
+        Vec3(-f32::ln(transmittance.x()), -f32::ln(transmittance.y()), -f32::ln(transmittance.z())) / distance
+    }
+
+    /// The index of refraction seen by a ray of `wavelength`.
+    fn index_of_refraction(&self, wavelength: f32) -> f32 {
+        match &self.dispersion {
+            Some(dispersion) if wavelength > 0.0 => dispersion.index_of_refraction(wavelength),
+            _ => self.index_of_refraction
+        }
+    }
+
     /// Use Schlick's approximation for reflectance.
     fn reflectance(cosine: f32, ref_idx: f32) -> f32 {
         let r0 = (1.0 - ref_idx) / (1.0 + ref_idx);
@@ -35,50 +104,101 @@ impl Dielectric {
 
 impl Material for Lambertian {
     fn scatter(&self, _r_in: Ray, record: HitRecord) -> Option<Scatter> {
-        let scatter_direction = if (record.normal + vec3::random_unit_vector()).near_zero() {
-            record.normal
-        } else {
-            record.normal + vec3::random_unit_vector()
-        };
-
-        let scattered = Ray(record.p, scatter_direction, _r_in.time());
+        let pdf = CosinePdf::new(record.normal);
         let attenuation = self.albedo;
 
-        Some(Scatter{r: scattered, attenuation})
+        Some(Scatter::Bsdf(Box::new(SampledBsdf { pdf, attenuation, normal: record.normal })))
     }
-} 
+}
 
-impl Material for Metal {
-    fn scatter(&self, r_in: Ray, record: HitRecord) -> Option<Scatter> {
+impl Metal {
+    /// Reflects `r_in` off the metal with `attenuation` in place of the albedo.
+    /// A perfect mirror is specular. A fuzzy one has a density, so that it can
+    /// be combined with light sampling. Rays scattered below the surface are
+    /// absorbed.
+    pub fn reflect(&self, r_in: Ray, record: HitRecord, attenuation: Color) -> Option<Scatter> {
         let reflected = r_in.direction().unit_vector().reflect(record.normal);
-        let scattered = Ray(record.p, reflected + random_in_unit_sphere() * f32::min(self.fuzz, 1.0), r_in.time());
-        let attenuation = self.albedo;
+        let fuzz = f32::min(self.fuzz, 1.0);
+
+        if fuzz > 0.0 {
+            let pdf = FuzzyReflectionPdf { reflected, fuzz };
+            return Some(Scatter::Bsdf(Box::new(SampledBsdf { pdf, attenuation, normal: record.normal })));
+        }
 
+        let scattered = Ray(record.p, reflected, r_in.time(), r_in.wavelength());
         if scattered.direction().dot(record.normal) > 0.0 {
-            Some(Scatter{r: scattered, attenuation})
+            Some(Scatter::Specular { r: scattered, attenuation })
         } else {
             None
         }
     }
 }
 
-impl Material for Dielectric {
-    fn scatter(&self, r_in: Ray, record: HitRecord) -> Option<Scatter> { 
-        let attenuation = Vec3(1.0, 1.0, 1.0);
-        let refraction_ratio = if record.front_face { 1.0 / self.index_of_refraction } else { self.index_of_refraction};
+impl Material for Metal {
+    fn scatter(&self, r_in: Ray, record: HitRecord) -> Option<Scatter> {
+        self.reflect(r_in, record, self.albedo)
+    }
+}
+
+impl Dielectric {
+    /// Reflects or refracts `r_in`, with the reflectance per channel given by
+    /// `reflectance(cos_theta, n_incident, n_transmitted)`. The reflection is
+    /// picked with the mean reflectance, and the attenuation makes up for it.
+    ///
+    /// A ray hitting a back face has traveled inside the glass since its
+    /// origin, and is attenuated by Beer–Lambert's law over that distance.
+    pub fn scatter_with_reflectance<F>(&self, r_in: Ray, record: HitRecord, reflectance: F) -> Option<Scatter>
+    where
+        F: Fn(f32, f32, f32) -> Color
+    {
+        let absorbed = if record.front_face {
+            Vec3(1.0, 1.0, 1.0)
+        } else {
+            let distance = record.t * r_in.direction().length();
+            let a = self.absorption * distance;
+            Vec3(f32::exp(-a.x()), f32::exp(-a.y()), f32::exp(-a.z()))
+        };
+        let index_of_refraction = self.index_of_refraction(r_in.wavelength());
+        let (n_incident, n_transmitted) = if record.front_face { (1.0, index_of_refraction) } else { (index_of_refraction, 1.0) };
+        let refraction_ratio = n_incident / n_transmitted;
         let unit_direction = r_in.direction().unit_vector();
         let cos_theta = f32::min( (-unit_direction).dot(record.normal), 1.0);
         let sin_theta = f32::sqrt(1.0 - cos_theta * cos_theta);
         let cannot_refract = refraction_ratio * sin_theta > 1.0;
 
-        let direction = if cannot_refract || Dielectric::reflectance(cos_theta, refraction_ratio) > random::<f32>() {
-            unit_direction.reflect(record.normal)
+        let (direction, attenuation) = if cannot_refract {
+            (unit_direction.reflect(record.normal), absorbed)
         } else {
-            unit_direction.refract(record.normal, refraction_ratio)
+            let r = reflectance(cos_theta, n_incident, n_transmitted);
+            let p = (r.x() + r.y() + r.z()) / 3.0;
+            if p > random::<f32>() {
+                (unit_direction.reflect(record.normal), absorbed * r / p)
+            } else {
+                (unit_direction.refract(record.normal, refraction_ratio), absorbed * (Vec3(1.0, 1.0, 1.0) - r) / (1.0 - p))
+            }
         };
 
-        let scattred = Ray(record.p, direction, r_in.time());
+        let scattred = Ray(record.p, direction, r_in.time(), r_in.wavelength());
+
+        Some(Scatter::Specular { r: scattred, attenuation })
+    }
+}
+
+impl Material for Dielectric {
+    fn scatter(&self, r_in: Ray, record: HitRecord) -> Option<Scatter> { 
+        self.scatter_with_reflectance(r_in, record, |cos_theta, n_incident, n_transmitted| {
+            let r = Dielectric::reflectance(cos_theta, n_incident / n_transmitted);
+            Vec3(r, r, r)
+        })
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, _r_in: Ray, _record: HitRecord) -> Option<Scatter> {
+        None
+    }
 
-        Some(Scatter { r: scattred, attenuation })
+    fn emitted(&self, _r_in: Ray, _record: HitRecord) -> Color {
+        self.emit
     }
 }
@@ -0,0 +1,145 @@
+use std::f32::consts::PI;
+use crate::vec3::{self, Vec3, Color};
+use crate::ray::Ray;
+use crate::hit::HitRecord;
+use crate::material::{Material, Scatter, Dielectric};
+use crate::pdf::Pdf;
+use crate::bsdf::Bsdf;
+use rand::random;
+
+/// A participating medium of constant density, with absorption and
+/// scattering coefficients per channel, per unit length.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HomogeneousMedium {
+    pub sigma_a: Color,
+    pub sigma_s: Color
+}
+
+/// What happens to a ray traveling through a medium towards a surface.
+pub enum MediumEvent {
+    /// The ray scatters after `distance`. `weight` is the throughput of the
+    /// segment, over the density of sampling it.
+    Scatter { distance: f32, weight: Color },
+    /// The ray reaches the surface.
+    Pass { weight: Color }
+}
+
+/// The isotropic phase function: scattering in a medium is equally likely in
+/// every direction.
+pub struct IsotropicPhase;
+
+/// A volume filled with `medium`, like smoke or fog. Its boundary is
+/// invisible: rays cross it unchanged, and only the medium inside shows.
+pub struct ConstantMedium {
+    pub medium: HomogeneousMedium
+}
+
+/// A translucent material, like skin, wax, marble or milk. Light is refracted
+/// into the object by a smooth dielectric boundary of `index_of_refraction`,
+/// and walks through `medium` inside until it leaves the object again.
+pub struct Subsurface {
+    pub index_of_refraction: f32,
+    pub medium: HomogeneousMedium
+}
+
+impl HomogeneousMedium {
+    /// A medium of a given `density` of particles, that reflect `albedo` of the
+    /// light they meet and absorb the rest.
+    pub fn from_density(density: f32, albedo: Color) -> Self {
+        HomogeneousMedium::from_mean_free_path(albedo, Vec3(1.0, 1.0, 1.0) / density)
+    }
+
+    /// A medium where light travels `mean_free_path` on average between two
+    /// collisions, and keeps `albedo` of its energy at each collision.
+    pub fn from_mean_free_path(albedo: Color, mean_free_path: Color) -> Self {
+        let sigma_t = Vec3(1.0 / mean_free_path.x(), 1.0 / mean_free_path.y(), 1.0 / mean_free_path.z());
+        HomogeneousMedium { sigma_a: sigma_t * (Vec3(1.0, 1.0, 1.0) - albedo), sigma_s: sigma_t * albedo }
+    }
+
+    fn sigma_t(&self) -> Color {
+        self.sigma_a + self.sigma_s
+    }
+
+    /// The fraction of light left after traveling `distance` in the medium.
+    pub fn transmittance(&self, distance: f32) -> Color {
+        let tau = self.sigma_t() * distance;
+        Vec3(f32::exp(-tau.x()), f32::exp(-tau.y()), f32::exp(-tau.z()))
+    }
+
+    /// Samples where a ray scatters on its way to a surface `distance` away.
+    ///
+    /// Collision distances are drawn with the extinction of a channel picked
+    /// at random, so that a medium with different coefficients per channel
+    /// is sampled well in all of them. The density is the average over the
+    /// channels.
+    pub fn sample(&self, distance: f32) -> MediumEvent {
+        let sigma_t = self.sigma_t();
+        let channel = match random::<f32>() * 3.0 {
+            c if c < 1.0 => sigma_t.x(),
+            c if c < 2.0 => sigma_t.y(),
+            _ => sigma_t.z()
+        };
+        let t = if channel > 0.0 { -f32::ln(1.0 - random::<f32>()) / channel } else { f32::INFINITY };
+        let mean = |c: Color| (c.x() + c.y() + c.z()) / 3.0;
+
+        if t < distance {
+            let transmittance = self.transmittance(t);
+            let pdf = mean(sigma_t * transmittance);
+            MediumEvent::Scatter { distance: t, weight: self.sigma_s * transmittance / pdf }
+        } else if distance.is_finite() {
+            let transmittance = self.transmittance(distance);
+            MediumEvent::Pass { weight: transmittance / mean(transmittance) }
+        } else {
+            MediumEvent::Pass { weight: Vec3(0.0, 0.0, 0.0) }
+        }
+    }
+
+    /// The medium with both coefficients transformed by `f`, like their
+    /// upsampling at the wavelength of a ray in spectral mode.
+    pub fn map<F: Fn(Color) -> Color>(self, f: F) -> Self {
+        HomogeneousMedium { sigma_a: f(self.sigma_a), sigma_s: f(self.sigma_s) }
+    }
+}
+
+impl Pdf for IsotropicPhase {
+    fn value(&self, _direction: Vec3) -> f32 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn generate(&self) -> Vec3 {
+        vec3::random_unit_vector()
+    }
+}
+
+impl Bsdf for IsotropicPhase {
+    fn eval(&self, _direction: Vec3) -> Color {
+        let value = 1.0 / (4.0 * PI);
+        Vec3(value, value, value)
+    }
+}
+
+impl Material for ConstantMedium {
+    fn scatter(&self, r_in: Ray, record: HitRecord) -> Option<Scatter> {
+        let r = Ray(record.p, r_in.direction(), r_in.time(), r_in.wavelength());
+        Some(Scatter::Specular { r, attenuation: Vec3(1.0, 1.0, 1.0) })
+    }
+
+    fn medium(&self) -> Option<HomogeneousMedium> {
+        Some(self.medium)
+    }
+
+    fn is_interface(&self) -> bool {
+        true
+    }
+}
+
+impl Material for Subsurface {
+    fn scatter(&self, r_in: Ray, record: HitRecord) -> Option<Scatter> {
+        let boundary = Dielectric { index_of_refraction: self.index_of_refraction, dispersion: None, absorption: Vec3(0.0, 0.0, 0.0) };
+        boundary.scatter(r_in, record)
+    }
+
+    fn medium(&self) -> Option<HomogeneousMedium> {
+        Some(self.medium)
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use crate::vec3::{Vec3, Point3D};
+
+/// A mesh of polygons with any number of sides, like the control cages
+/// modelers send. Faces list the indices of their vertices in `positions`,
+/// counterclockwise seen from the outside.
+///
+/// `creases` gives the sharpness of edges, keyed by their two vertices in
+/// increasing order: the number of subdivision levels they stay sharp for,
+/// or infinity for edges that are always sharp. Boundary edges are always
+/// sharp.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PolygonMesh {
+    pub positions: Vec<Point3D>,
+    pub faces: Vec<Vec<usize>>,
+    pub creases: HashMap<(usize, usize), f32>
+}
+
+/// The key of the edge between vertices `a` and `b`.
+pub fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    if a < b { (a, b) } else { (b, a) }
+}
+
+impl PolygonMesh {
+    /// Reads the vertices and faces of a Wavefront OBJ file. Texture
+    /// coordinates, normals, groups and materials are ignored.
+    pub fn load_obj<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        PolygonMesh::parse_obj(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parses the vertices and faces of the Wavefront OBJ `text`.
+    pub fn parse_obj(text: &str) -> io::Result<Self> {
+        let invalid = |line: usize, message: &str| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line + 1, message));
+        let mut mesh = PolygonMesh::default();
+
+        for (number, line) in text.lines().enumerate() {
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                Some("v") => {
+                    let coordinates: Vec<f32> = fields.take(3).map(|f| f.parse::<f32>()).collect::<Result<_, _>>().map_err(|_| invalid(number, "invalid vertex"))?;
+                    if coordinates.len() != 3 {
+                        return Err(invalid(number, "vertex needs 3 coordinates"));
+                    }
+                    mesh.positions.push(Vec3(coordinates[0], coordinates[1], coordinates[2]));
+                }
+                Some("f") => {
+                    let mut face = Vec::new();
+                    for field in fields {
+                        // "v", "v/vt", "v//vn" or "v/vt/vn", 1-based, or
+                        // negative to count back from the last vertex.
+                        let index: isize = field.split('/').next().unwrap_or("").parse().map_err(|_| invalid(number, "invalid face index"))?;
+                        let index = if index < 0 { mesh.positions.len() as isize + index } else { index - 1 };
+                        if index < 0 || index as usize >= mesh.positions.len() {
+                            return Err(invalid(number, "face index out of range"));
+                        }
+                        face.push(index as usize);
+                    }
+                    if face.len() < 3 {
+                        return Err(invalid(number, "face needs at least 3 vertices"));
+                    }
+                    mesh.faces.push(face);
+                }
+                _ => {}
+            }
+        }
+        Ok(mesh)
+    }
+
+    /// The sharpness of the edge between vertices `a` and `b`, 0 if smooth.
+    pub fn sharpness(&self, a: usize, b: usize) -> f32 {
+        self.creases.get(&edge_key(a, b)).copied().unwrap_or(0.0)
+    }
+
+    /// The normal of face `f`, with Newell's method, so that faces that are
+    /// not planar get the average of their sides. Its length is the area of
+    /// the face.
+    pub fn face_normal(&self, f: usize) -> Vec3 {
+        let face = &self.faces[f];
+        face.iter().enumerate().fold(Vec3(0.0, 0.0, 0.0), |normal, (i, &a)| {
+            let (p, q) = (self.positions[a], self.positions[face[(i + 1) % face.len()]]);
+            normal + p.cross(q) / 2.0
+        })
+    }
+
+    /// The faces on each side of every edge.
+    pub fn edge_faces(&self) -> HashMap<(usize, usize), Vec<usize>> {
+        let mut edges: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (f, face) in self.faces.iter().enumerate() {
+            for (i, &a) in face.iter().enumerate() {
+                let b = face[(i + 1) % face.len()];
+                edges.entry(edge_key(a, b)).or_default().push(f);
+            }
+        }
+        edges
+    }
+}
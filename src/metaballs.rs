@@ -0,0 +1,140 @@
+use crate::vec3::{Vec3, Point3D};
+use crate::ray::Ray;
+use crate::hit::{HitRecord, Hittable};
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::sphere::get_sphere_uv;
+use crate::polynomial;
+
+/// A ball of a `Metaballs` object. Its field is `weight` at its center and
+/// falls to 0 at `radius`, with (1 - d² / radius²)³ at distance d. The
+/// center moves linearly from `center_0` at `time_0` to `center_1` at
+/// `time_1`, like a `MovingSphere`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ball {
+    pub center_0: Point3D,
+    pub center_1: Point3D,
+    pub time_0: f32,
+    pub time_1: f32,
+    pub radius: f32,
+    pub weight: f32
+}
+
+impl Ball {
+    /// A ball that does not move.
+    pub fn new(center: Point3D, radius: f32, weight: f32) -> Self {
+        Ball { center_0: center, center_1: center, time_0: 0.0, time_1: 1.0, radius, weight }
+    }
+
+    /// The center at `time`.
+    pub fn center(&self, time: f32) -> Point3D {
+        self.center_0 + (self.center_1 - self.center_0) * ((time - self.time_0) / (self.time_1 - self.time_0))
+    }
+}
+
+/// Blobby objects: the surface where the sum of the fields of `balls` is
+/// `threshold`. Balls close to each other merge smoothly, like drops of
+/// liquid. A ball alone is a sphere of radius
+/// `radius * sqrt(1 - cbrt(threshold / weight))`.
+///
+/// Along a ray, the field of a ball is a polynomial of degree 6, and it is 0
+/// outside the sphere of the ball. Balls whose spheres the ray misses are
+/// skipped, and the ray is split where it enters and leaves the others. On
+/// each piece, the sum of the fields of the balls it is in is a polynomial,
+/// whose first root is found by `polynomial::solve_in_interval`.
+pub struct Metaballs {
+    pub balls: Vec<Ball>,
+    pub threshold: f32,
+    pub material: Box<dyn Material>
+}
+
+fn multiply(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut product = vec![0.0; a.len() + b.len() - 1];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate() {
+            product[i + j] += x * y;
+        }
+    }
+    product
+}
+
+impl Metaballs {
+    /// The unit normal at `p` at `time`: the opposite of the gradient of the
+    /// field, which decreases outwards.
+    fn normal(&self, p: Point3D, time: f32) -> Vec3 {
+        let gradient = self.balls.iter().fold(Vec3(0.0, 0.0, 0.0), |gradient, ball| {
+            let offset = p - ball.center(time);
+            let falloff = 1.0 - offset.length_squared() / (ball.radius * ball.radius);
+            if falloff <= 0.0 {
+                return gradient;
+            }
+            gradient + offset * (-6.0 * ball.weight * falloff * falloff / (ball.radius * ball.radius))
+        });
+        -gradient.unit_vector()
+    }
+}
+
+impl Hittable for Metaballs {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let time = r.time();
+        let a = r.direction().length_squared();
+
+        // Where the ray is inside the sphere of each ball.
+        let mut inside: Vec<(f32, f32, &Ball)> = Vec::new();
+        for ball in &self.balls {
+            let oc = r.origin() - ball.center(time);
+            let half_b = oc.dot(r.direction());
+            let c = oc.length_squared() - ball.radius * ball.radius;
+            let discriminant = half_b * half_b - a * c;
+            if discriminant <= 0.0 {
+                continue;
+            }
+            let sqrtd = discriminant.sqrt();
+            let (enter, exit) = (((-half_b - sqrtd) / a).max(t_min), ((-half_b + sqrtd) / a).min(t_max));
+            if enter < exit {
+                inside.push((enter, exit, ball));
+            }
+        }
+
+        let mut bounds: Vec<f32> = inside.iter().flat_map(|&(enter, exit, _)| [enter, exit]).collect();
+        bounds.sort_by(|x, y| x.total_cmp(y));
+        for piece in bounds.windows(2) {
+            let (start, end) = (piece[0], piece[1]);
+            if start >= end {
+                continue;
+            }
+            let middle = 0.5 * (start + end);
+
+            // The field minus the threshold, along the ray from `start`.
+            let mut field = vec![0.0; 7];
+            field[6] = -self.threshold as f64;
+            for &(_, _, ball) in inside.iter().filter(|&&(enter, exit, _)| enter <= middle && middle <= exit) {
+                let oc = r.at(start) - ball.center(time);
+                let radius2 = (ball.radius * ball.radius) as f64;
+                let falloff = [-a as f64 / radius2, -2.0 * oc.dot(r.direction()) as f64 / radius2, 1.0 - oc.length_squared() as f64 / radius2];
+                for (sum, c) in field.iter_mut().zip(multiply(&multiply(&falloff, &falloff), &falloff)) {
+                    *sum += ball.weight as f64 * c;
+                }
+            }
+
+            if let Some(&s) = polynomial::solve_in_interval(&field, 0.0, (end - start) as f64).first() {
+                let t = start + s as f32;
+                let outward_normal = self.normal(r.at(t), time);
+                let (u, v) = get_sphere_uv(outward_normal);
+                let dpdu = Vec3(outward_normal.z(), 0.0, -outward_normal.x());
+                return Some(HitRecord::new(r, t, outward_normal, u, v, dpdu, &*self.material));
+            }
+        }
+        None
+    }
+
+    /// The spheres of the balls, at both times.
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb> {
+        self.balls.iter()
+            .flat_map(|ball| {
+                let radius = Vec3(ball.radius, ball.radius, ball.radius);
+                [Aabb::around(ball.center(time0), radius), Aabb::around(ball.center(time1), radius)]
+            })
+            .reduce(Aabb::surrounding_box)
+    }
+}
@@ -0,0 +1,361 @@
+use crate::vec3::{Vec3, Color};
+use crate::ray::Ray;
+use crate::hit::HitRecord;
+use crate::material::{Material, Scatter};
+use crate::texture::Texture;
+use crate::onb::Onb;
+use crate::pdf::Pdf;
+use crate::bsdf::Bsdf;
+use rand::random;
+use std::f32::consts::PI;
+
+/// Below this alpha the distribution is so narrow that surfaces are
+/// treated as perfectly smooth.
+const SMOOTH_ALPHA: f32 = 1e-3;
+
+/// The GGX (Trowbridge-Reitz) distribution of microfacet normals, with
+/// roughness `alpha_x` along the tangent and `alpha_y` along the bitangent.
+///
+/// Directions are expressed in the local shading frame, where x is the
+/// tangent and z is the surface normal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ggx {
+    pub alpha_x: f32,
+    pub alpha_y: f32
+}
+
+impl Ggx {
+    pub fn isotropic(alpha: f32) -> Self {
+        Ggx { alpha_x: alpha, alpha_y: alpha }
+    }
+
+    /// Maps a perceptual `roughness` in [0, 1] to alpha = roughness², so that
+    /// highlights widen evenly as roughness increases.
+    pub fn from_roughness(roughness: f32) -> Self {
+        let roughness = roughness.clamp(0.0, 1.0);
+        Ggx::isotropic(roughness * roughness)
+    }
+
+    pub fn is_smooth(&self) -> bool {
+        f32::max(self.alpha_x, self.alpha_y) < SMOOTH_ALPHA
+    }
+
+    /// Density of microfacets with normal `m`, per unit of projected area.
+    pub fn d(&self, m: Vec3) -> f32 {
+        if m.z() <= 0.0 {
+            return 0.0;
+        }
+        let e = (m.x() / self.alpha_x).powi(2) + (m.y() / self.alpha_y).powi(2) + m.z() * m.z();
+        1.0 / (PI * self.alpha_x * self.alpha_y * e * e)
+    }
+
+    /// Smith's auxiliary function: the ratio of microfacet area hidden from `w`
+    /// to the area visible from it.
+    fn lambda(&self, w: Vec3) -> f32 {
+        let cos2_theta = w.z() * w.z();
+        if cos2_theta == 0.0 {
+            return f32::INFINITY;
+        }
+        let alpha2_tan2_theta = ((self.alpha_x * w.x()).powi(2) + (self.alpha_y * w.y()).powi(2)) / cos2_theta;
+        (-1.0 + f32::sqrt(1.0 + alpha2_tan2_theta)) / 2.0
+    }
+
+    /// Fraction of microfacets visible from `w` (Smith masking).
+    pub fn g1(&self, w: Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Fraction of microfacets visible from both `wo` and `wi`
+    /// (height-correlated Smith shadowing-masking).
+    pub fn g(&self, wo: Vec3, wi: Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Samples a microfacet normal among those visible from `wo`, with
+    /// Heitz's method: `wo` is stretched to the configuration where both alphas are 1,
+    /// a point is picked on the projected hemisphere, and the normal found
+    /// there is unstretched.
+    pub fn sample_visible_normal(&self, wo: Vec3) -> Vec3 {
+        let vh = Vec3(self.alpha_x * wo.x(), self.alpha_y * wo.y(), wo.z()).unit_vector();
+
+        let lensq = vh.x() * vh.x() + vh.y() * vh.y();
+        let t1 = if lensq > 0.0 { Vec3(-vh.y(), vh.x(), 0.0) / lensq.sqrt() } else { Vec3(1.0, 0.0, 0.0) };
+        let t2 = vh.cross(t1);
+
+        let r = f32::sqrt(random::<f32>());
+        let phi = 2.0 * PI * random::<f32>();
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z());
+        let p2 = (1.0 - s) * f32::sqrt(1.0 - p1 * p1) + s * r * phi.sin();
+
+        let nh = t1 * p1 + t2 * p2 + vh * f32::sqrt(f32::max(0.0, 1.0 - p1 * p1 - p2 * p2));
+        Vec3(self.alpha_x * nh.x(), self.alpha_y * nh.y(), f32::max(1e-6, nh.z())).unit_vector()
+    }
+
+    /// Density of `sample_visible_normal` returning `m`.
+    pub fn visible_pdf(&self, wo: Vec3, m: Vec3) -> f32 {
+        if wo.z() <= 0.0 {
+            return 0.0;
+        }
+        self.g1(wo) * f32::max(0.0, wo.dot(m)) * self.d(m) / wo.z()
+    }
+}
+
+/// Schlick's approximation of the Fresnel reflectance, from the reflectance
+/// at normal incidence `f0`.
+pub fn fresnel_schlick(f0: Color, cos_theta: f32) -> Color {
+    f0 + (Vec3(1.0, 1.0, 1.0) - f0) * (1.0 - cos_theta).clamp(0.0, 1.0).powi(5)
+}
+
+/// Exact Fresnel reflectance of unpolarized light at the interface between two
+/// dielectrics. `eta` is the index of refraction of the transmitted side over
+/// the index of the incident side.
+pub fn fresnel_dielectric(cos_theta_i: f32, eta: f32) -> f32 {
+    let cos_theta_i = cos_theta_i.clamp(0.0, 1.0);
+    let sin2_theta_t = (1.0 - cos_theta_i * cos_theta_i) / (eta * eta);
+    if sin2_theta_t >= 1.0 {
+        // Total internal reflection.
+        return 1.0;
+    }
+    let cos_theta_t = f32::sqrt(1.0 - sin2_theta_t);
+
+    let r_parallel = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let r_perpendicular = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+    (r_parallel * r_parallel + r_perpendicular * r_perpendicular) / 2.0
+}
+
+/// Exact Fresnel reflectance of unpolarized light on a conductor, per channel.
+/// `eta` and `k` are the real and imaginary parts of the conductor's complex
+/// index of refraction, relative to the incident medium.
+pub fn fresnel_conductor(cos_theta_i: f32, eta: Color, k: Color) -> Color {
+    let channel = |eta: f32, k: f32| -> f32 {
+        let cos2_theta = cos_theta_i.clamp(0.0, 1.0).powi(2);
+        let sin2_theta = 1.0 - cos2_theta;
+        let t0 = eta * eta - k * k - sin2_theta;
+        let a2_plus_b2 = f32::sqrt(t0 * t0 + 4.0 * eta * eta * k * k);
+        let t1 = a2_plus_b2 + cos2_theta;
+        let a = f32::sqrt(0.5 * (a2_plus_b2 + t0));
+        let t2 = 2.0 * cos_theta_i.clamp(0.0, 1.0) * a;
+        let r_s = (t1 - t2) / (t1 + t2);
+        let t3 = cos2_theta * a2_plus_b2 + sin2_theta * sin2_theta;
+        let t4 = t2 * sin2_theta;
+        let r_p = r_s * (t3 - t4) / (t3 + t4);
+        0.5 * (r_p + r_s)
+    };
+    Vec3(channel(eta.x(), k.x()), channel(eta.y(), k.y()), channel(eta.z(), k.z()))
+}
+
+/// The Fresnel term of a conductor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fresnel {
+    /// Schlick's approximation, from the reflectance at normal incidence.
+    Schlick(Color),
+    /// The exact equations, from a complex index of refraction.
+    Conductor { eta: Color, k: Color }
+}
+
+impl Fresnel {
+    pub fn evaluate(&self, cos_theta: f32) -> Color {
+        match self {
+            Fresnel::Schlick(f0) => fresnel_schlick(*f0, cos_theta),
+            Fresnel::Conductor { eta, k } => fresnel_conductor(cos_theta, *eta, *k)
+        }
+    }
+}
+
+/// Scatters `r_in` off a conductor with normals distributed by `distribution`:
+/// a mirror reflection when the distribution is smooth, a `ConductorBsdf` otherwise.
+pub fn scatter_conductor(r_in: Ray, record: HitRecord, distribution: Ggx, fresnel: Fresnel) -> Option<Scatter> {
+    let unit_direction = r_in.direction().unit_vector();
+
+    if distribution.is_smooth() {
+        let cos_theta = f32::min((-unit_direction).dot(record.normal), 1.0);
+        let scattered = Ray(record.p, unit_direction.reflect(record.normal), r_in.time(), r_in.wavelength());
+        return Some(Scatter::Specular { r: scattered, attenuation: fresnel.evaluate(cos_theta) });
+    }
+
+    let frame = record.frame();
+    let wo = frame.to_local(-unit_direction);
+    Some(Scatter::Bsdf(Box::new(ConductorBsdf { frame, wo, distribution, fresnel })))
+}
+
+/// A rough metal: microscopic mirrors with normals distributed by GGX.
+///
+/// `albedo` is the reflectance at normal incidence, used with Schlick's
+/// approximation. `roughness` is read from the first channel of its texture.
+pub struct RoughConductor {
+    pub albedo: Box<dyn Texture>,
+    pub roughness: Box<dyn Texture>
+}
+
+/// Brushed metal: a rough conductor whose microfacets are stretched along
+/// one direction. `alpha_x` is the GGX roughness along the tangent of the
+/// surface, and `alpha_y` along the bitangent.
+///
+/// `albedo` is the reflectance at normal incidence, used with Schlick's
+/// approximation.
+pub struct AnisotropicConductor {
+    pub albedo: Box<dyn Texture>,
+    pub alpha_x: f32,
+    pub alpha_y: f32
+}
+
+/// Rough glass: microscopic facets with normals distributed by GGX, that
+/// reflect or refract according to the exact dielectric Fresnel equations.
+pub struct RoughDielectric {
+    pub index_of_refraction: f32,
+    pub roughness: Box<dyn Texture>
+}
+
+/// The BSDF of a rough conductor at a hit point. `wo` is the direction
+/// towards the viewer, in the local `frame`.
+pub struct ConductorBsdf {
+    pub frame: Onb,
+    pub wo: Vec3,
+    pub distribution: Ggx,
+    pub fresnel: Fresnel
+}
+
+/// The BSDF of `RoughDielectric` at a hit point. `eta` is the index of
+/// refraction on the other side of the surface over the index on the side of `wo`.
+pub struct DielectricBsdf {
+    pub frame: Onb,
+    pub wo: Vec3,
+    pub distribution: Ggx,
+    pub eta: f32
+}
+
+impl Pdf for ConductorBsdf {
+    fn value(&self, direction: Vec3) -> f32 {
+        let wi = self.frame.to_local(direction.unit_vector());
+        if wi.z() <= 0.0 {
+            return 0.0;
+        }
+        let m = (self.wo + wi).unit_vector();
+        self.distribution.visible_pdf(self.wo, m) / (4.0 * self.wo.dot(m))
+    }
+
+    fn generate(&self) -> Vec3 {
+        let m = self.distribution.sample_visible_normal(self.wo);
+        self.frame.local((-self.wo).reflect(m))
+    }
+}
+
+impl Bsdf for ConductorBsdf {
+    fn eval(&self, direction: Vec3) -> Color {
+        let wi = self.frame.to_local(direction.unit_vector());
+        if wi.z() <= 0.0 {
+            return Vec3(0.0, 0.0, 0.0);
+        }
+        let m = (self.wo + wi).unit_vector();
+        let fresnel = self.fresnel.evaluate(self.wo.dot(m));
+        fresnel * (self.distribution.d(m) * self.distribution.g(self.wo, wi) / (4.0 * self.wo.z()))
+    }
+}
+
+impl DielectricBsdf {
+    /// The microfacet normal that refracts `wo` into `wi`, facing the outer side,
+    /// or `None` when `wi` can not be reached by refraction.
+    fn refraction_normal(&self, wi: Vec3) -> Option<Vec3> {
+        let m = (self.wo + wi * self.eta).unit_vector();
+        let m = if m.z() < 0.0 { -m } else { m };
+        if self.wo.dot(m) <= 0.0 || wi.dot(m) >= 0.0 {
+            return None;
+        }
+        Some(m)
+    }
+}
+
+impl Pdf for DielectricBsdf {
+    fn value(&self, direction: Vec3) -> f32 {
+        let wi = self.frame.to_local(direction.unit_vector());
+        if wi.z() > 0.0 {
+            let m = (self.wo + wi).unit_vector();
+            let fresnel = fresnel_dielectric(self.wo.dot(m), self.eta);
+            return self.distribution.visible_pdf(self.wo, m) / (4.0 * self.wo.dot(m)) * fresnel;
+        }
+        match self.refraction_normal(wi) {
+            Some(m) => {
+                let fresnel = fresnel_dielectric(self.wo.dot(m), self.eta);
+                let denom = (wi.dot(m) + self.wo.dot(m) / self.eta).powi(2);
+                self.distribution.visible_pdf(self.wo, m) * f32::abs(wi.dot(m)) / denom * (1.0 - fresnel)
+            }
+            None => 0.0
+        }
+    }
+
+    fn generate(&self) -> Vec3 {
+        let m = self.distribution.sample_visible_normal(self.wo);
+        let fresnel = fresnel_dielectric(self.wo.dot(m), self.eta);
+        let wi = if random::<f32>() < fresnel {
+            (-self.wo).reflect(m)
+        } else {
+            (-self.wo).refract(m, 1.0 / self.eta)
+        };
+        self.frame.local(wi)
+    }
+}
+
+impl Bsdf for DielectricBsdf {
+    /// Like `Dielectric`, refraction does not scale radiance by the ratio of
+    /// the indices of refraction: the factors cancel out when a path enters
+    /// and leaves an object.
+    fn eval(&self, direction: Vec3) -> Color {
+        let wi = self.frame.to_local(direction.unit_vector());
+        let value = if wi.z() > 0.0 {
+            let m = (self.wo + wi).unit_vector();
+            let fresnel = fresnel_dielectric(self.wo.dot(m), self.eta);
+            fresnel * self.distribution.d(m) * self.distribution.g(self.wo, wi) / (4.0 * self.wo.z())
+        } else {
+            match self.refraction_normal(wi) {
+                Some(m) => {
+                    let fresnel = fresnel_dielectric(self.wo.dot(m), self.eta);
+                    let denom = (wi.dot(m) + self.wo.dot(m) / self.eta).powi(2);
+                    (1.0 - fresnel) * self.distribution.d(m) * self.distribution.g(self.wo, wi)
+                        * f32::abs(wi.dot(m) * self.wo.dot(m)) / (self.wo.z() * denom)
+                }
+                None => 0.0
+            }
+        };
+        Vec3(value, value, value)
+    }
+}
+
+impl Material for RoughConductor {
+    fn scatter(&self, r_in: Ray, record: HitRecord) -> Option<Scatter> {
+        let f0 = self.albedo.value(record.u, record.v, record.p);
+        let distribution = Ggx::from_roughness(self.roughness.value(record.u, record.v, record.p).x());
+        scatter_conductor(r_in, record, distribution, Fresnel::Schlick(f0))
+    }
+}
+
+impl Material for AnisotropicConductor {
+    fn scatter(&self, r_in: Ray, record: HitRecord) -> Option<Scatter> {
+        let f0 = self.albedo.value(record.u, record.v, record.p);
+        let distribution = Ggx { alpha_x: self.alpha_x, alpha_y: self.alpha_y };
+        scatter_conductor(r_in, record, distribution, Fresnel::Schlick(f0))
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(&self, r_in: Ray, record: HitRecord) -> Option<Scatter> {
+        let eta = if record.front_face { self.index_of_refraction } else { 1.0 / self.index_of_refraction };
+        let distribution = Ggx::from_roughness(self.roughness.value(record.u, record.v, record.p).x());
+        let unit_direction = r_in.direction().unit_vector();
+
+        if distribution.is_smooth() {
+            let cos_theta = f32::min((-unit_direction).dot(record.normal), 1.0);
+            let direction = if random::<f32>() < fresnel_dielectric(cos_theta, eta) {
+                unit_direction.reflect(record.normal)
+            } else {
+                unit_direction.refract(record.normal, 1.0 / eta)
+            };
+            let scattered = Ray(record.p, direction, r_in.time(), r_in.wavelength());
+            return Some(Scatter::Specular { r: scattered, attenuation: Vec3(1.0, 1.0, 1.0) });
+        }
+
+        let frame = record.frame();
+        let wo = frame.to_local(-unit_direction);
+        Some(Scatter::Bsdf(Box::new(DielectricBsdf { frame, wo, distribution, eta })))
+    }
+}
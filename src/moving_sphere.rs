@@ -1,7 +1,9 @@
 use crate::vec3::Vec3;
 use crate::ray::Ray;
 use crate::hit::{HitRecord, Hittable};
+use crate::aabb::Aabb;
 use crate::material::Material;
+use crate::sphere::get_sphere_uv;
 
 pub struct MovingSphere {
     pub center_0: Vec3,
@@ -12,9 +14,18 @@ pub struct MovingSphere {
     pub time_1: f32,
 }
 
+impl MovingSphere {
+    /// The center at `time`, moving linearly with the velocity that takes it
+    /// from `center_0` to `center_1` between `time_0` and `time_1`. It is at
+    /// `center_0` at `time_1`.
+    pub fn center(&self, time: f32) -> Vec3 {
+        self.center_0 + (self.center_1 - self.center_0) * ((time - self.time_1) / (self.time_1 - self.time_0))
+    }
+}
+
 impl Hittable for MovingSphere {
     fn hit<'a>(&'a self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'a>> {
-        let center =  self.center_0 + (self.center_1 - self.center_0) * ((r.time() - self.time_1) / (self.time_1 - self.time_0));
+        let center = self.center(r.time());
 
         let oc = r.origin() - center;
         let a = r.direction().length_squared();
@@ -35,28 +46,16 @@ impl Hittable for MovingSphere {
             }
         }
         
-        let p = r.at(root);
-        let t = root;
-        // TODO in the book the following logic is implemented as a 
-        // setter in the HitRecord struct. Refactor if we
-        // need this logic in more places.
-        let outward_normal = (p - center) / self.radius;
-        let front_face = r.direction().dot(outward_normal) < 0.0;
-        let normal = if front_face { 
-            outward_normal 
-        }   else {
-            - outward_normal
-        };
-       
-        let material = &*self.material;
-        let record = HitRecord {
-            p,
-            normal,
-            front_face,
-            t,
-            material
-        };
+        let outward_normal = (r.at(root) - center) / self.radius;
+        let (u, v) = get_sphere_uv(outward_normal);
+        // u turns around the Y axis.
+        let dpdu = Vec3(outward_normal.z(), 0.0, -outward_normal.x());
+
+        Some(HitRecord::new(r, root, outward_normal, u, v, dpdu, &*self.material))
+    }
 
-        return Some(record);
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb> {
+        let radius = Vec3(self.radius, self.radius, self.radius);
+        Some(Aabb::surrounding_box(Aabb::around(self.center(time0), radius), Aabb::around(self.center(time1), radius)))
     }
 }
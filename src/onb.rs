@@ -0,0 +1,30 @@
+use crate::vec3::Vec3;
+
+/// An orthonormal basis. Used to express directions sampled around
+/// the z axis in the local frame of a surface normal `w`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3
+}
+
+impl Onb {
+    pub fn build_from_w(n: Vec3) -> Self {
+        let w = n.unit_vector();
+        // Pick any vector that is not parallel to w.
+        let a = if f32::abs(w.x()) > 0.9 { Vec3(0.0, 1.0, 0.0) } else { Vec3(1.0, 0.0, 0.0) };
+        let v = w.cross(a).unit_vector();
+        let u = w.cross(v);
+        Onb { u, v, w }
+    }
+
+    pub fn local(&self, a: Vec3) -> Vec3 {
+        self.u * a.x() + self.v * a.y() + self.w * a.z()
+    }
+
+    /// The coordinates of a world direction `a` in this basis.
+    pub fn to_local(self, a: Vec3) -> Vec3 {
+        Vec3(a.dot(self.u), a.dot(self.v), a.dot(self.w))
+    }
+}
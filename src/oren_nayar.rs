@@ -0,0 +1,79 @@
+use std::f32::consts::PI;
+use crate::vec3::{Vec3, Color};
+use crate::ray::Ray;
+use crate::hit::HitRecord;
+use crate::material::{Material, Scatter};
+use crate::bsdf::Bsdf;
+use crate::pdf::{Pdf, CosinePdf};
+
+/// A rough diffuse surface, made of V-shaped Lambertian facets whose slopes
+/// have a standard deviation of `sigma` radians, with the Oren–Nayar model.
+/// Rough surfaces scatter light back towards it, like clay or the moon.
+/// A `sigma` of 0 is `Lambertian`.
+pub struct OrenNayar {
+    pub albedo: Color,
+    pub sigma: f32
+}
+
+/// The BSDF of `OrenNayar` at a hit point, towards the unit direction `wo`.
+/// Directions are sampled with a cosine distribution.
+struct OrenNayarBsdf {
+    pdf: CosinePdf,
+    albedo: Color,
+    normal: Vec3,
+    wo: Vec3,
+    a: f32,
+    b: f32
+}
+
+impl Pdf for OrenNayarBsdf {
+    fn value(&self, direction: Vec3) -> f32 {
+        self.pdf.value(direction)
+    }
+
+    fn generate(&self) -> Vec3 {
+        self.pdf.generate()
+    }
+}
+
+impl Bsdf for OrenNayarBsdf {
+    fn eval(&self, direction: Vec3) -> Color {
+        let wi = direction.unit_vector();
+        let cos_i = wi.dot(self.normal).min(1.0);
+        let cos_o = self.wo.dot(self.normal).min(1.0);
+        if cos_i <= 0.0 || cos_o <= 0.0 {
+            return Vec3(0.0, 0.0, 0.0);
+        }
+
+        // Cosine of the azimuth between both directions, from their
+        // projections on the tangent plane.
+        let tangent_i = wi - self.normal * cos_i;
+        let tangent_o = self.wo - self.normal * cos_o;
+        let lengths = f32::sqrt(tangent_i.length_squared() * tangent_o.length_squared());
+        let cos_phi = if lengths > 0.0 { (tangent_i.dot(tangent_o) / lengths).max(0.0) } else { 0.0 };
+
+        // sin α tan β, with α the larger and β the smaller polar angle.
+        let sin_i = f32::sqrt(1.0 - cos_i * cos_i);
+        let sin_o = f32::sqrt(1.0 - cos_o * cos_o);
+        let sin_alpha_tan_beta = if cos_i > cos_o { sin_o * sin_i / cos_i } else { sin_i * sin_o / cos_o };
+
+        self.albedo * ((self.a + self.b * cos_phi * sin_alpha_tan_beta) * cos_i / PI)
+    }
+}
+
+impl Material for OrenNayar {
+    fn scatter(&self, r_in: Ray, record: HitRecord) -> Option<Scatter> {
+        let sigma2 = self.sigma * self.sigma;
+        let a = 1.0 - sigma2 / (2.0 * (sigma2 + 0.33));
+        let b = 0.45 * sigma2 / (sigma2 + 0.09);
+
+        Some(Scatter::Bsdf(Box::new(OrenNayarBsdf {
+            pdf: CosinePdf::new(record.normal),
+            albedo: self.albedo,
+            normal: record.normal,
+            wo: -r_in.direction().unit_vector(),
+            a,
+            b
+        })))
+    }
+}
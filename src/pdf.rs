@@ -0,0 +1,132 @@
+use crate::vec3::{self, Vec3, Point3D};
+use crate::onb::Onb;
+use crate::hit::Hittable;
+use crate::background::Background;
+use rand::random;
+
+/// A probability density function over directions.
+///
+/// `value` returns the density of a direction, `generate` draws
+/// a random direction distributed according to that density.
+pub trait Pdf {
+    fn value(&self, direction: Vec3) -> f32;
+    fn generate(&self) -> Vec3;
+}
+
+/// Cosine weighted directions around a surface normal.
+pub struct CosinePdf {
+    pub uvw: Onb
+}
+
+impl CosinePdf {
+    pub fn new(w: Vec3) -> Self {
+        CosinePdf { uvw: Onb::build_from_w(w) }
+    }
+}
+
+impl Pdf for CosinePdf {
+    fn value(&self, direction: Vec3) -> f32 {
+        let cosine = direction.unit_vector().dot(self.uvw.w);
+        if cosine <= 0.0 { 0.0 } else { cosine / std::f32::consts::PI }
+    }
+
+    fn generate(&self) -> Vec3 {
+        self.uvw.local(vec3::random_cosine_direction())
+    }
+}
+
+/// Directions from `origin` towards a hittable, usually a light.
+pub struct HittablePdf<'a> {
+    pub origin: Point3D,
+    pub hittable: &'a dyn Hittable
+}
+
+impl Pdf for HittablePdf<'_> {
+    fn value(&self, direction: Vec3) -> f32 {
+        self.hittable.pdf_value(self.origin, direction)
+    }
+
+    fn generate(&self) -> Vec3 {
+        self.hittable.random(self.origin)
+    }
+}
+
+/// Directions of a fuzzy reflection: the unit `reflected` direction plus a
+/// random point in a sphere of radius `fuzz`, as `Metal` scatters.
+pub struct FuzzyReflectionPdf {
+    pub reflected: Vec3,
+    pub fuzz: f32
+}
+
+impl Pdf for FuzzyReflectionPdf {
+    /// Points are uniform in the fuzz sphere, so the density of a direction is
+    /// the volume of the fuzz sphere swept by a unit solid angle around it,
+    /// ∫ t² dt between the distances t0 and t1 where the direction enters and
+    /// leaves the sphere, over the volume of the sphere.
+    fn value(&self, direction: Vec3) -> f32 {
+        let b = direction.unit_vector().dot(self.reflected);
+        let discriminant = b * b - 1.0 + self.fuzz * self.fuzz;
+        if discriminant <= 0.0 {
+            return 0.0;
+        }
+        let t1 = b + discriminant.sqrt();
+        let t0 = f32::max(b - discriminant.sqrt(), 0.0);
+        if t1 <= 0.0 {
+            return 0.0;
+        }
+
+        (t1.powi(3) - t0.powi(3)) / (4.0 * std::f32::consts::PI * self.fuzz.powi(3))
+    }
+
+    fn generate(&self) -> Vec3 {
+        self.reflected + vec3::random_in_unit_sphere() * self.fuzz
+    }
+}
+
+/// Directions of light coming from the background.
+pub struct BackgroundPdf<'a> {
+    pub background: &'a Background
+}
+
+impl Pdf for BackgroundPdf<'_> {
+    fn value(&self, direction: Vec3) -> f32 {
+        self.background.pdf_value(direction)
+    }
+
+    fn generate(&self) -> Vec3 {
+        self.background.random()
+    }
+}
+
+/// A 50/50 mixture of two densities.
+pub struct MixturePdf<'a> {
+    pub p0: Box<dyn Pdf + 'a>,
+    pub p1: Box<dyn Pdf + 'a>
+}
+
+impl Pdf for MixturePdf<'_> {
+    fn value(&self, direction: Vec3) -> f32 {
+        0.5 * self.p0.value(direction) + 0.5 * self.p1.value(direction)
+    }
+
+    fn generate(&self) -> Vec3 {
+        if random::<f32>() < 0.5 {
+            self.p0.generate()
+        } else {
+            self.p1.generate()
+        }
+    }
+}
+
+/// Multiple importance sampling weight, with the power heuristic (beta = 2),
+/// of a sample drawn with density `pdf` that could also have been drawn by
+/// another strategy with density `other_pdf`.
+pub fn power_heuristic(pdf: f32, other_pdf: f32) -> f32 {
+    // Squaring infinite densities would give inf / inf.
+    if pdf.is_infinite() || other_pdf.is_infinite() {
+        return if other_pdf.is_infinite() { 0.0 } else { 1.0 };
+    }
+    let f = pdf * pdf;
+    let g = other_pdf * other_pdf;
+    if f + g == 0.0 { 0.0 } else { f / (f + g) }
+}
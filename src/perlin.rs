@@ -0,0 +1,104 @@
+use crate::vec3::{self, Vec3, Point3D};
+use rand::Rng;
+
+const POINT_COUNT: usize = 256;
+
+/// Perlin noise: random unit vectors on a lattice, interpolated smoothly
+/// between lattice points, so the noise varies smoothly in [-1, 1].
+pub struct Perlin {
+    random_vectors: Vec<Vec3>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>
+}
+
+impl Perlin {
+    pub fn new() -> Self {
+        let random_vectors = (0..POINT_COUNT).map(|_| vec3::random(Some(-1.0), Some(1.0)).unit_vector()).collect();
+        Perlin {
+            random_vectors,
+            perm_x: Perlin::generate_perm(),
+            perm_y: Perlin::generate_perm(),
+            perm_z: Perlin::generate_perm()
+        }
+    }
+
+    pub fn noise(&self, p: Point3D) -> f32 {
+        let u = p.x() - p.x().floor();
+        let v = p.y() - p.y().floor();
+        let w = p.z() - p.z().floor();
+
+        let i = p.x().floor() as i32;
+        let j = p.y().floor() as i32;
+        let k = p.z().floor() as i32;
+
+        let mut c = [[[Vec3(0.0, 0.0, 0.0); 2]; 2]; 2];
+        for (di, plane) in c.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, corner) in row.iter_mut().enumerate() {
+                    let index = self.perm_x[((i + di as i32) & 255) as usize]
+                        ^ self.perm_y[((j + dj as i32) & 255) as usize]
+                        ^ self.perm_z[((k + dk as i32) & 255) as usize];
+                    *corner = self.random_vectors[index];
+                }
+            }
+        }
+
+        Perlin::perlin_interp(&c, u, v, w)
+    }
+
+    /// Sum of `depth` octaves of noise, each with twice the frequency and
+    /// half the weight of the previous one.
+    pub fn turb(&self, p: Point3D, depth: u32) -> f32 {
+        let mut accum = 0.0;
+        let mut temp_p = p;
+        let mut weight = 1.0;
+
+        for _ in 0..depth {
+            accum += weight * self.noise(temp_p);
+            weight *= 0.5;
+            temp_p = temp_p * 2.0;
+        }
+
+        accum.abs()
+    }
+
+    fn generate_perm() -> Vec<usize> {
+        let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+        let mut rng = rand::thread_rng();
+        for i in (1..POINT_COUNT).rev() {
+            let target = rng.gen_range(0..=i);
+            p.swap(i, target);
+        }
+        p
+    }
+
+    /// Trilinear interpolation of the gradients at the corners `c`, with
+    /// Hermite smoothing of `u`, `v` and `w`.
+    fn perlin_interp(c: &[[[Vec3; 2]; 2]; 2], u: f32, v: f32, w: f32) -> f32 {
+        let uu = u * u * (3.0 - 2.0 * u);
+        let vv = v * v * (3.0 - 2.0 * v);
+        let ww = w * w * (3.0 - 2.0 * w);
+        let mut accum = 0.0;
+
+        for (i, plane) in c.iter().enumerate() {
+            for (j, row) in plane.iter().enumerate() {
+                for (k, corner) in row.iter().enumerate() {
+                    let (fi, fj, fk) = (i as f32, j as f32, k as f32);
+                    let weight = Vec3(u - fi, v - fj, w - fk);
+                    accum += (fi * uu + (1.0 - fi) * (1.0 - uu))
+                        * (fj * vv + (1.0 - fj) * (1.0 - vv))
+                        * (fk * ww + (1.0 - fk) * (1.0 - ww))
+                        * corner.dot(weight);
+                }
+            }
+        }
+        accum
+    }
+}
+
+impl Default for Perlin {
+    fn default() -> Self {
+        Perlin::new()
+    }
+}
@@ -0,0 +1,91 @@
+use crate::vec3::{self, Vec3, Point3D};
+use crate::ray::Ray;
+use crate::hit::{HitRecord, Hittable};
+use crate::aabb::Aabb;
+use crate::aarect::area_pdf_value;
+use crate::material::Material;
+use crate::onb::Onb;
+
+/// The infinite plane through `point`, facing the unit `normal`. Its surface
+/// coordinates repeat every unit of length, so textures tile across it.
+pub struct Plane {
+    pub point: Point3D,
+    pub normal: Vec3,
+    pub material: Box<dyn Material>
+}
+
+/// A disk of `radius` around `center`, facing the unit `normal`. It can be
+/// used as a light. u turns around the center and v goes out to the rim.
+pub struct Disk {
+    pub center: Point3D,
+    pub normal: Vec3,
+    pub radius: f32,
+    pub material: Box<dyn Material>
+}
+
+/// Distance along `r` to the plane through `point` with `normal`, if it is
+/// within [`t_min`, `t_max`].
+fn plane_hit(r: Ray, point: Point3D, normal: Vec3, t_min: f32, t_max: f32) -> Option<f32> {
+    let denominator = r.direction().dot(normal);
+    if denominator.abs() < 1e-8 {
+        return None;
+    }
+    let t = (point - r.origin()).dot(normal) / denominator;
+    if t < t_min || t > t_max {
+        return None;
+    }
+    Some(t)
+}
+
+impl Hittable for Plane {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let t = plane_hit(r, self.point, self.normal, t_min, t_max)?;
+        let frame = Onb::build_from_w(self.normal);
+        let local = frame.to_local(r.at(t) - self.point);
+        let (u, v) = (local.x() - local.x().floor(), local.y() - local.y().floor());
+
+        Some(HitRecord::new(r, t, self.normal, u, v, frame.u, &*self.material))
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        Some(Aabb::infinite())
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let t = plane_hit(r, self.center, self.normal, t_min, t_max)?;
+        let frame = Onb::build_from_w(self.normal);
+        let local = frame.to_local(r.at(t) - self.center);
+        let distance = f32::sqrt(local.x() * local.x() + local.y() * local.y());
+        if distance > self.radius {
+            return None;
+        }
+        let phi = f32::atan2(local.y(), local.x());
+        let u = (phi + std::f32::consts::PI) / (2.0 * std::f32::consts::PI);
+        let v = distance / self.radius;
+        let dpdu = frame.local(Vec3(-local.y(), local.x(), 0.0));
+
+        Some(HitRecord::new(r, t, self.normal, u, v, dpdu, &*self.material))
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        Some(Aabb::disk(self.center, self.normal, self.radius))
+    }
+
+    fn pdf_value(&self, origin: Point3D, v: Vec3) -> f32 {
+        match self.hit(Ray(origin, v, 0.0, 0.0), 0.001, f32::INFINITY) {
+            Some(record) => {
+                let area = std::f32::consts::PI * self.radius * self.radius;
+                area_pdf_value(v, record.t, self.normal, area)
+            }
+            None => 0.0
+        }
+    }
+
+    fn random(&self, origin: Point3D) -> Vec3 {
+        let frame = Onb::build_from_w(self.normal);
+        let random_point = self.center + frame.local(vec3::random_in_unit_disk() * self.radius);
+        random_point - origin
+    }
+}
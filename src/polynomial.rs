@@ -0,0 +1,294 @@
+//! Real roots of polynomials, in f64: closed forms up to degree four, and
+//! bracketed roots within an interval for any degree.
+//! Intersections with curved surfaces lose much precision in f32, so their
+//! equations are solved in double precision.
+
+/// Real roots of a x² + b x + c, in increasing order. Degenerates to the
+/// linear equation when `a` is 0. Avoids the cancellation of the textbook
+/// formula when b² is much larger than 4ac.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a == 0.0 {
+        return if b == 0.0 { vec![] } else { vec![-c / b] };
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return vec![];
+    }
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    let (x0, x1) = if q == 0.0 { (0.0, 0.0) } else { (q / a, c / q) };
+    if x0 < x1 { vec![x0, x1] } else { vec![x1, x0] }
+}
+
+/// The largest real root of the monic cubic x³ + a x² + b x + c, with
+/// Cardano's formula when there is a single real root, and the
+/// trigonometric method when there are three.
+fn largest_cubic_root(a: f64, b: f64, c: f64) -> f64 {
+    // With x = t - a / 3: t³ + p t + q = 0.
+    let p = b - a * a / 3.0;
+    let q = 2.0 * a * a * a / 27.0 - a * b / 3.0 + c;
+    let discriminant = q * q / 4.0 + p * p * p / 27.0;
+
+    let t = if discriminant > 0.0 {
+        let sqrt_discriminant = discriminant.sqrt();
+        f64::cbrt(-q / 2.0 + sqrt_discriminant) + f64::cbrt(-q / 2.0 - sqrt_discriminant)
+    } else if p == 0.0 {
+        0.0
+    } else {
+        let m = 2.0 * f64::sqrt(-p / 3.0);
+        let cos = (3.0 * q / (p * m)).clamp(-1.0, 1.0);
+        m * f64::cos(cos.acos() / 3.0)
+    };
+    let x = t - a / 3.0;
+
+    // One Newton step removes most of the error of the closed forms.
+    let f = ((x + a) * x + b) * x + c;
+    let df = (3.0 * x + 2.0 * a) * x + b;
+    if df != 0.0 { x - f / df } else { x }
+}
+
+/// Value and derivative of the polynomial with `coefficients`, from the
+/// highest degree, at `x`.
+fn evaluate(coefficients: &[f64], x: f64) -> (f64, f64) {
+    coefficients.iter().fold((0.0, 0.0), |(f, df), &c| (f * x + c, df * x + f))
+}
+
+/// A bound on the rounding error of `evaluate` at `x`.
+fn evaluation_error(coefficients: &[f64], x: f64) -> f64 {
+    let magnitude = coefficients.iter().fold(0.0, |sum, &c| sum * x.abs() + c.abs());
+    2.0 * coefficients.len() as f64 * f64::EPSILON * magnitude
+}
+
+/// Real roots of c4 x⁴ + c3 x³ + c2 x² + c1 x + c0, in increasing order.
+///
+/// Ferrari's method factors the quartic into two quadratics using the
+/// largest root of its resolvent cubic, which is the best conditioned.
+/// The roots are then polished with Newton's method on the quartic itself,
+/// which makes up for the precision lost in the closed forms.
+pub fn solve_quartic(c4: f64, c3: f64, c2: f64, c1: f64, c0: f64) -> Vec<f64> {
+    if c4 == 0.0 {
+        return vec![];
+    }
+    let (a, b, c, d) = (c3 / c4, c2 / c4, c1 / c4, c0 / c4);
+
+    // With x = y - a / 4: y⁴ + p y² + q y + r = 0.
+    let a2 = a * a;
+    let p = b - 3.0 * a2 / 8.0;
+    let q = c - a * b / 2.0 + a2 * a / 8.0;
+    let r = d - a * c / 4.0 + a2 * b / 16.0 - 3.0 * a2 * a2 / 256.0;
+
+    let mut roots = if q.abs() < 1e-12 {
+        // Biquadratic: a quadratic in y².
+        solve_quadratic(1.0, p, r)
+            .into_iter()
+            .filter(|&z| z >= 0.0)
+            .flat_map(|z| [-z.sqrt(), z.sqrt()])
+            .collect::<Vec<f64>>()
+    } else {
+        // y⁴ + p y² + q y + r = (y² + p/2 + m)² - 2m (y - q / 4m)², where m
+        // is a root of the resolvent cubic, positive since q is not 0.
+        let m = largest_cubic_root(p, p * p / 4.0 - r, -q * q / 8.0);
+        if m <= 0.0 {
+            return vec![];
+        }
+        let s = f64::sqrt(2.0 * m);
+        let mut roots = solve_quadratic(1.0, -s, p / 2.0 + m + q / (2.0 * s));
+        roots.extend(solve_quadratic(1.0, s, p / 2.0 + m - q / (2.0 * s)));
+        roots
+    };
+
+    let coefficients = [1.0, a, b, c, d];
+    for root in roots.iter_mut() {
+        let mut x = *root - a / 4.0;
+        for _ in 0..4 {
+            let (f, df) = evaluate(&coefficients, x);
+            if df == 0.0 {
+                break;
+            }
+            let next = x - f / df;
+            // Stop as soon as a step does not improve the residual.
+            if evaluate(&coefficients, next).0.abs() >= f.abs() {
+                break;
+            }
+            x = next;
+        }
+        *root = x;
+    }
+    roots.sort_by(|x, y| x.total_cmp(y));
+    roots
+}
+
+/// Real roots of the polynomial with `coefficients`, from the highest degree,
+/// within [`low`, `high`], in increasing order.
+///
+/// The roots of the derivative, found the same way, split the interval into
+/// pieces where the polynomial is monotonic, so each has at most one root.
+/// Roots are then refined by Newton's method, kept inside their bracket by
+/// bisection. Unlike sampling, no root can be missed, however close roots are.
+/// Double roots, where the polynomial touches 0 without changing sign, are
+/// found at the roots of the derivative, when the polynomial is within
+/// rounding error of 0 there.
+pub fn solve_in_interval(coefficients: &[f64], low: f64, high: f64) -> Vec<f64> {
+    let leading = coefficients.iter().position(|&c| c != 0.0).unwrap_or(coefficients.len());
+    let coefficients = &coefficients[leading..];
+    let degree = coefficients.len().saturating_sub(1);
+    if degree == 0 || low > high {
+        return vec![];
+    }
+    if degree == 1 {
+        let root = -coefficients[1] / coefficients[0];
+        return if (low..=high).contains(&root) { vec![root] } else { vec![] };
+    }
+
+    let derivative: Vec<f64> = coefficients[..degree].iter().enumerate().map(|(i, &c)| c * (degree - i) as f64).collect();
+    let mut bounds = vec![low];
+    bounds.extend(solve_in_interval(&derivative, low, high));
+    bounds.push(high);
+
+    let mut roots: Vec<f64> = Vec::new();
+    for piece in bounds.windows(2) {
+        let (mut a, mut b) = (piece[0], piece[1]);
+        let (fa, fb) = (evaluate(coefficients, a).0, evaluate(coefficients, b).0);
+        let root = if fa == 0.0 {
+            a
+        } else if fb == 0.0 {
+            b
+        } else if (fa > 0.0) == (fb > 0.0) {
+            if b < high && fb.abs() <= evaluation_error(coefficients, b) {
+                b
+            } else {
+                continue;
+            }
+        } else {
+            let mut x = 0.5 * (a + b);
+            for _ in 0..64 {
+                let (f, df) = evaluate(coefficients, x);
+                if f == 0.0 {
+                    break;
+                }
+                if (f > 0.0) == (fa > 0.0) { a = x } else { b = x }
+                let newton = x - f / df;
+                let next = if newton > a && newton < b { newton } else { 0.5 * (a + b) };
+                if (next - x).abs() <= 1e-14 * x.abs().max(1.0) {
+                    x = next;
+                    break;
+                }
+                x = next;
+            }
+            x
+        };
+        // A root at the end of a piece is also at the start of the next.
+        if roots.last() != Some(&root) {
+            roots.push(root);
+        }
+    }
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_roots(roots: &[f64], expected: &[f64]) {
+        assert_eq!(roots.len(), expected.len(), "roots {:?}, expected {:?}", roots, expected);
+        for (&root, &e) in roots.iter().zip(expected) {
+            assert!((root - e).abs() < 1e-9, "roots {:?}, expected {:?}", roots, expected);
+        }
+    }
+
+    /// Rounding can split a double root into two close roots, or leave it
+    /// single, so only where the roots are is checked.
+    fn assert_roots_near(roots: &[f64], expected: &[f64]) {
+        let near = |x: f64, points: &[f64]| points.iter().any(|&p| (x - p).abs() < 1e-6);
+        assert!(roots.iter().all(|&x| near(x, expected)), "roots {:?}, expected {:?}", roots, expected);
+        assert!(expected.iter().all(|&x| near(x, roots)), "roots {:?}, expected {:?}", roots, expected);
+    }
+
+    #[test]
+    fn quartic_with_four_roots() {
+        // (x + 2)(x + 0.5)(x - 1)(x - 3)
+        assert_roots(&solve_quartic(1.0, -1.5, -6.0, 3.5, 3.0), &[-2.0, -0.5, 1.0, 3.0]);
+    }
+
+    #[test]
+    fn quartic_with_two_roots() {
+        // (x - 1)(x - 2)(x² + 1)
+        assert_roots(&solve_quartic(2.0, -6.0, 6.0, -6.0, 4.0), &[1.0, 2.0]);
+    }
+
+    #[test]
+    fn biquadratic() {
+        // (x² - 1)(x² - 4)
+        assert_roots(&solve_quartic(1.0, 0.0, -5.0, 0.0, 4.0), &[-2.0, -1.0, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn quartic_with_double_roots() {
+        // (x - 1)²(x - 2)²
+        assert_roots_near(&solve_quartic(1.0, -6.0, 13.0, -12.0, 4.0), &[1.0, 2.0]);
+    }
+
+    #[test]
+    fn quartic_without_roots() {
+        // (x² + 1)(x² + 2)
+        assert_roots(&solve_quartic(1.0, 0.0, 3.0, 0.0, 2.0), &[]);
+        assert_roots(&solve_quartic(0.0, 1.0, 0.0, 0.0, -1.0), &[]);
+    }
+
+    #[test]
+    fn roots_in_interval() {
+        // (x + 2)(x + 0.5)(x - 1)(x - 3)
+        let quartic = [1.0, -1.5, -6.0, 3.5, 3.0];
+        assert_roots(&solve_in_interval(&quartic, -10.0, 10.0), &[-2.0, -0.5, 1.0, 3.0]);
+        assert_roots(&solve_in_interval(&quartic, 0.0, 2.0), &[1.0]);
+        assert_roots(&solve_in_interval(&quartic, 1.5, 2.5), &[]);
+    }
+
+    #[test]
+    fn roots_of_high_degree() {
+        // (x - 0.1)(x - 0.2)(x - 0.3)(x - 0.4)(x - 0.5)(x - 0.6)
+        let mut sextic = vec![1.0];
+        for root in [0.1, 0.2, 0.3, 0.4, 0.5, 0.6] {
+            sextic.push(0.0);
+            for i in (1..sextic.len()).rev() {
+                sextic[i] -= root * sextic[i - 1];
+            }
+        }
+        assert_roots(&solve_in_interval(&sextic, 0.0, 1.0), &[0.1, 0.2, 0.3, 0.4, 0.5, 0.6]);
+    }
+
+    #[test]
+    fn double_roots_in_interval() {
+        // (x - 1)²(x - 3)
+        assert_roots(&solve_in_interval(&[1.0, -5.0, 7.0, -3.0], 0.0, 4.0), &[1.0, 3.0]);
+        // (x - 0.3)²
+        assert_roots(&solve_in_interval(&[1.0, -0.6, 0.09], 0.0, 1.0), &[0.3]);
+        // (x - 0.7)² and (x - 0.1)², whose rounded coefficients move the
+        // minimum just off 0.
+        assert_roots_near(&solve_in_interval(&[1.0, -1.4, 0.49], 0.0, 1.0), &[0.7]);
+        assert_roots_near(&solve_in_interval(&[1.0, -0.2, 0.01], 0.0, 1.0), &[0.1]);
+        // (x - 1/3)²(x - 0.7)
+        let r = 1.0 / 3.0;
+        assert_roots_near(&solve_in_interval(&[1.0, -2.0 * r - 0.7, r * r + 1.4 * r, -0.7 * r * r], 0.0, 1.0), &[r, 0.7]);
+        // (x² + 1)² has no real root.
+        assert_roots(&solve_in_interval(&[1.0, 0.0, 2.0, 0.0, 1.0], -2.0, 2.0), &[]);
+    }
+
+    #[test]
+    fn roots_at_the_ends_of_the_interval() {
+        // (x - 1)(x - 2)
+        let quadratic = [1.0, -3.0, 2.0];
+        assert_roots(&solve_in_interval(&quadratic, 1.0, 2.0), &[1.0, 2.0]);
+        assert_roots(&solve_in_interval(&quadratic, 1.0, 1.5), &[1.0]);
+        assert_roots(&solve_in_interval(&quadratic, 1.5, 2.0), &[2.0]);
+        assert_roots(&solve_in_interval(&[2.0, -4.0], 2.0, 3.0), &[2.0]);
+    }
+
+    #[test]
+    fn empty_interval() {
+        let quadratic = [1.0, -3.0, 2.0];
+        assert_roots(&solve_in_interval(&quadratic, 2.5, 0.5), &[]);
+        assert_roots(&solve_in_interval(&quadratic, 1.0, 1.0), &[1.0]);
+        assert_roots(&solve_in_interval(&[3.0], 0.0, 1.0), &[]);
+        assert_roots(&solve_in_interval(&[0.0, 0.0, 1.0, -0.5], 0.0, 1.0), &[0.5]);
+    }
+}
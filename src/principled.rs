@@ -0,0 +1,287 @@
+use crate::vec3::{self, Vec3, Color};
+use crate::ray::Ray;
+use crate::hit::HitRecord;
+use crate::material::{Material, Scatter};
+use crate::texture::{Texture, SolidColor, ScaledTexture, ChannelTexture, solid};
+use crate::onb::Onb;
+use crate::pdf::Pdf;
+use crate::bsdf::Bsdf;
+use crate::microfacet::{Ggx, DielectricBsdf, fresnel_schlick};
+use rand::random;
+use std::f32::consts::PI;
+use std::rc::Rc;
+
+/// Principled surfaces keep a small roughness, so that all of their lobes
+/// can be evaluated for light sampling.
+const MIN_ROUGHNESS: f32 = 0.05;
+
+/// The Disney "principled" BSDF: one material whose parameters blend between
+/// plastics, metals, glass and coated surfaces.
+///
+/// Every parameter is a texture; scalar parameters are read from the first
+/// channel and expected in [0, 1].
+///
+/// - `base_color`: diffuse color for dielectrics, reflectance for metals.
+/// - `metallic`: blends from a dielectric (0) to a metal (1).
+/// - `roughness`: perceptual roughness of the specular and transmission lobes.
+/// - `specular`: reflectance at normal incidence of dielectrics, 0.5 is 4%.
+/// - `specular_tint`: tints that reflectance towards `base_color`.
+/// - `sheen`, `sheen_tint`: extra grazing reflection, for cloth.
+/// - `clearcoat`, `clearcoat_gloss`: a second, white and glossy specular lobe.
+/// - `transmission`: blends the dielectric towards rough glass.
+/// - `subsurface`: flattens the diffuse lobe, approximating light scattered
+///   under the surface.
+pub struct Principled {
+    pub base_color: Box<dyn Texture>,
+    pub metallic: Box<dyn Texture>,
+    pub roughness: Box<dyn Texture>,
+    pub specular: Box<dyn Texture>,
+    pub specular_tint: Box<dyn Texture>,
+    pub sheen: Box<dyn Texture>,
+    pub sheen_tint: Box<dyn Texture>,
+    pub clearcoat: Box<dyn Texture>,
+    pub clearcoat_gloss: Box<dyn Texture>,
+    pub transmission: Box<dyn Texture>,
+    pub subsurface: Box<dyn Texture>,
+    pub index_of_refraction: f32
+}
+
+impl Principled {
+    /// A rough dielectric of color `base_color`, with every other parameter at
+    /// its default.
+    pub fn new(base_color: Box<dyn Texture>) -> Self {
+        Principled {
+            base_color,
+            metallic: solid(0.0),
+            roughness: solid(0.5),
+            specular: solid(0.5),
+            specular_tint: solid(0.0),
+            sheen: solid(0.0),
+            sheen_tint: solid(0.5),
+            clearcoat: solid(0.0),
+            clearcoat_gloss: solid(1.0),
+            transmission: solid(0.0),
+            subsurface: solid(0.0),
+            index_of_refraction: 1.5
+        }
+    }
+
+    /// Maps a glTF 2.0 metallic-roughness material.
+    ///
+    /// Factors multiply their textures, as in glTF. The metallic-roughness texture
+    /// stores roughness in its second channel and metallic in its third. Textures
+    /// are expected in linear space. glTF dielectrics reflect 4% at normal
+    /// incidence, which is the default `specular` of 0.5.
+    pub fn from_gltf_metallic_roughness(
+        base_color_factor: Color,
+        base_color_texture: Option<Box<dyn Texture>>,
+        metallic_factor: f32,
+        roughness_factor: f32,
+        metallic_roughness_texture: Option<Box<dyn Texture>>
+    ) -> Self {
+        let base_color: Box<dyn Texture> = match base_color_texture {
+            Some(texture) => Box::new(ScaledTexture { texture, scale: base_color_factor }),
+            None => Box::new(SolidColor { color: base_color_factor })
+        };
+        let mut material = Principled::new(base_color);
+
+        match metallic_roughness_texture {
+            Some(texture) => {
+                let texture: Rc<dyn Texture> = Rc::from(texture);
+                material.roughness = Box::new(ChannelTexture { texture: Box::new(texture.clone()), channel: 1, scale: roughness_factor });
+                material.metallic = Box::new(ChannelTexture { texture: Box::new(texture), channel: 2, scale: metallic_factor });
+            }
+            None => {
+                material.roughness = solid(roughness_factor);
+                material.metallic = solid(metallic_factor);
+            }
+        }
+        material
+    }
+}
+
+/// The principled BSDF evaluated at a hit point. Lobes are picked for
+/// sampling with probabilities that follow their approximate weight.
+struct PrincipledBsdf {
+    frame: Onb,
+    wo: Vec3,
+    base_color: Color,
+    metallic: f32,
+    specular_color: Color,
+    subsurface: f32,
+    sheen_color: Color,
+    clearcoat: f32,
+    transmission: f32,
+    distribution: Ggx,
+    clearcoat_alpha: f32,
+    glass: DielectricBsdf,
+    // Probabilities of sampling the diffuse, specular, clearcoat and glass lobes.
+    lobe_pdfs: [f32; 4]
+}
+
+fn luminance(c: Color) -> f32 {
+    0.3 * c.x() + 0.6 * c.y() + 0.1 * c.z()
+}
+
+fn mix(a: Color, b: Color, t: f32) -> Color {
+    a * (1.0 - t) + b * t
+}
+
+fn schlick_weight(cos_theta: f32) -> f32 {
+    (1.0 - cos_theta).clamp(0.0, 1.0).powi(5)
+}
+
+/// The GTR1 (Berry) distribution of the clearcoat lobe, which has a longer tail than GGX.
+fn gtr1(cos_theta_h: f32, alpha: f32) -> f32 {
+    let a2 = alpha * alpha;
+    (a2 - 1.0) / (PI * f32::ln(a2) * (1.0 + (a2 - 1.0) * cos_theta_h * cos_theta_h))
+}
+
+fn sample_gtr1(alpha: f32) -> Vec3 {
+    let a2 = alpha * alpha;
+    let cos_theta = f32::sqrt((1.0 - a2.powf(1.0 - random::<f32>())) / (1.0 - a2)).clamp(0.0, 1.0);
+    let sin_theta = f32::sqrt(1.0 - cos_theta * cos_theta);
+    let phi = 2.0 * PI * random::<f32>();
+    Vec3(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+impl PrincipledBsdf {
+    /// The diffuse, sheen, specular and clearcoat lobes, for `wi` above the surface.
+    fn eval_reflection(&self, wi: Vec3) -> Color {
+        let wo = self.wo;
+        let h = (wo + wi).unit_vector();
+        let cos_d = wi.dot(h);
+        let fl = schlick_weight(wi.z());
+        let fv = schlick_weight(wo.z());
+        let roughness = self.distribution.alpha_x.sqrt();
+
+        // Diffuse with retro-reflection at grazing angles, flattened towards
+        // the Hanrahan-Krueger approximation of subsurface scattering.
+        let fd90 = 0.5 + 2.0 * cos_d * cos_d * roughness;
+        let fd = (1.0 + (fd90 - 1.0) * fl) * (1.0 + (fd90 - 1.0) * fv);
+        let fss90 = cos_d * cos_d * roughness;
+        let fss = (1.0 + (fss90 - 1.0) * fl) * (1.0 + (fss90 - 1.0) * fv);
+        let ss = 1.25 * (fss * (1.0 / (wi.z() + wo.z()) - 0.5) + 0.5);
+        let diffuse_weight = (1.0 - self.metallic) * (1.0 - self.transmission);
+        let diffuse = (self.base_color * ((fd + (ss - fd) * self.subsurface) / PI)
+            + self.sheen_color * schlick_weight(cos_d)) * diffuse_weight * wi.z();
+
+        let specular = fresnel_schlick(self.specular_color, cos_d)
+            * (self.distribution.d(h) * self.distribution.g(wo, wi) / (4.0 * wo.z()))
+            * (1.0 - (1.0 - self.metallic) * self.transmission);
+
+        let clearcoat_g = Ggx::isotropic(0.25);
+        let clearcoat = 0.25 * self.clearcoat * gtr1(h.z(), self.clearcoat_alpha)
+            * (0.04 + 0.96 * schlick_weight(cos_d))
+            * clearcoat_g.g1(wo) * clearcoat_g.g1(wi) / (4.0 * wo.z());
+
+        diffuse + specular + Vec3(clearcoat, clearcoat, clearcoat)
+    }
+
+    /// Densities of each lobe sampling `wi`, for `wi` above the surface.
+    fn reflection_pdfs(&self, wi: Vec3) -> [f32; 3] {
+        let h = (self.wo + wi).unit_vector();
+        let diffuse = wi.z() / PI;
+        let specular = self.distribution.visible_pdf(self.wo, h) / (4.0 * self.wo.dot(h));
+        let clearcoat = gtr1(h.z(), self.clearcoat_alpha) * h.z() / (4.0 * self.wo.dot(h));
+        [diffuse, specular, clearcoat]
+    }
+}
+
+impl Pdf for PrincipledBsdf {
+    fn value(&self, direction: Vec3) -> f32 {
+        let wi = self.frame.to_local(direction.unit_vector());
+        let mut value = self.lobe_pdfs[3] * self.glass.value(direction);
+        if wi.z() > 0.0 {
+            let pdfs = self.reflection_pdfs(wi);
+            value += self.lobe_pdfs[0] * pdfs[0] + self.lobe_pdfs[1] * pdfs[1] + self.lobe_pdfs[2] * pdfs[2];
+        }
+        value
+    }
+
+    fn generate(&self) -> Vec3 {
+        let mut u = random::<f32>();
+        if u < self.lobe_pdfs[0] {
+            return self.frame.local(vec3::random_cosine_direction());
+        }
+        u -= self.lobe_pdfs[0];
+        if u < self.lobe_pdfs[1] {
+            let m = self.distribution.sample_visible_normal(self.wo);
+            return self.frame.local((-self.wo).reflect(m));
+        }
+        u -= self.lobe_pdfs[1];
+        if u < self.lobe_pdfs[2] {
+            let m = sample_gtr1(self.clearcoat_alpha);
+            return self.frame.local((-self.wo).reflect(m));
+        }
+        self.glass.generate()
+    }
+}
+
+impl Bsdf for PrincipledBsdf {
+    fn eval(&self, direction: Vec3) -> Color {
+        let wi = self.frame.to_local(direction.unit_vector());
+        let glass_weight = (1.0 - self.metallic) * self.transmission;
+        if wi.z() > 0.0 {
+            self.eval_reflection(wi) + self.glass.eval(direction) * glass_weight
+        } else {
+            // Light refracted through the surface is tinted by the base color.
+            self.glass.eval(direction) * self.base_color * glass_weight
+        }
+    }
+}
+
+impl Material for Principled {
+    fn scatter(&self, r_in: Ray, record: HitRecord) -> Option<Scatter> {
+        let (u, v, p) = (record.u, record.v, record.p);
+        let base_color = self.base_color.value(u, v, p);
+        let metallic = self.metallic.value(u, v, p).x();
+        let roughness = f32::max(self.roughness.value(u, v, p).x(), MIN_ROUGHNESS);
+        let specular = self.specular.value(u, v, p).x();
+        let specular_tint = self.specular_tint.value(u, v, p).x();
+        let sheen = self.sheen.value(u, v, p).x();
+        let sheen_tint = self.sheen_tint.value(u, v, p).x();
+        let clearcoat = self.clearcoat.value(u, v, p).x();
+        let clearcoat_gloss = self.clearcoat_gloss.value(u, v, p).x();
+        let transmission = self.transmission.value(u, v, p).x();
+        let subsurface = self.subsurface.value(u, v, p).x();
+
+        // Hue and saturation of the base color, for tints.
+        let white = Vec3(1.0, 1.0, 1.0);
+        let tint = if luminance(base_color) > 0.0 { base_color / luminance(base_color) } else { white };
+        let specular_color = mix(mix(white, tint, specular_tint) * (specular * 0.08), base_color, metallic);
+        let sheen_color = mix(white, tint, sheen_tint) * sheen;
+
+        let frame = record.frame();
+        let wo = frame.to_local(-r_in.direction().unit_vector());
+        let distribution = Ggx::from_roughness(roughness);
+        let eta = if record.front_face { self.index_of_refraction } else { 1.0 / self.index_of_refraction };
+        let glass = DielectricBsdf { frame, wo, distribution, eta };
+
+        let diffuse_weight = (1.0 - metallic) * (1.0 - transmission) * (luminance(base_color) + luminance(sheen_color));
+        let specular_weight = (1.0 - (1.0 - metallic) * transmission) * luminance(fresnel_schlick(specular_color, wo.z()));
+        let clearcoat_weight = 0.25 * clearcoat;
+        let glass_weight = (1.0 - metallic) * transmission;
+        let total = diffuse_weight + specular_weight + clearcoat_weight + glass_weight;
+        if total <= 0.0 {
+            return None;
+        }
+        let lobe_pdfs = [diffuse_weight / total, specular_weight / total, clearcoat_weight / total, glass_weight / total];
+
+        Some(Scatter::Bsdf(Box::new(PrincipledBsdf {
+            frame,
+            wo,
+            base_color,
+            metallic,
+            specular_color,
+            subsurface,
+            sheen_color,
+            clearcoat,
+            transmission,
+            distribution,
+            clearcoat_alpha: 0.1 + (0.001 - 0.1) * clearcoat_gloss,
+            glass,
+            lobe_pdfs
+        })))
+    }
+}
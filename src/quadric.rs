@@ -0,0 +1,103 @@
+use crate::vec3::{Vec3, Point3D};
+use crate::ray::Ray;
+use crate::hit::{HitRecord, Hittable};
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::sphere::get_sphere_uv;
+use crate::polynomial;
+
+/// The surface where
+/// a x² + b y² + c z² + d xy + e xz + f yz + g x + h y + i z + j = 0,
+/// with `coefficients` [a, b, c, d, e, f, g, h, i, j]. The outside is where
+/// the left side is positive.
+///
+/// Only the parts inside `clip`, when it is set, are kept, which turns
+/// infinite surfaces like paraboloids into dishes and tubes. Clipped
+/// surfaces are open: they can be seen from the inside.
+pub struct Quadric {
+    pub coefficients: [f32; 10],
+    pub clip: Option<Aabb>,
+    pub material: Box<dyn Material>
+}
+
+impl Quadric {
+    /// An ellipsoid around `center`, with semi-axes `radii` along x, y and z.
+    pub fn ellipsoid(center: Point3D, radii: Vec3, material: Box<dyn Material>) -> Self {
+        let k = Vec3(1.0 / (radii.x() * radii.x()), 1.0 / (radii.y() * radii.y()), 1.0 / (radii.z() * radii.z()));
+        let j = k.dot(center * center) - 1.0;
+        Quadric { coefficients: Quadric::centered(center, k, Vec3(0.0, 0.0, 0.0), j), clip: None, material }
+    }
+
+    /// A paraboloid around the vertical axis through `vertex`, opening upwards,
+    /// whose focus is `focal_length` above the vertex.
+    pub fn paraboloid(vertex: Point3D, focal_length: f32, material: Box<dyn Material>) -> Self {
+        // (x - x0)² + (z - z0)² = 4 f (y - y0)
+        let k = Vec3(1.0, 0.0, 1.0);
+        let j = vertex.x() * vertex.x() + vertex.z() * vertex.z() + 4.0 * focal_length * vertex.y();
+        Quadric { coefficients: Quadric::centered(vertex, k, Vec3(0.0, -4.0 * focal_length, 0.0), j), clip: None, material }
+    }
+
+    /// A hyperboloid of one sheet around the vertical axis through `center`,
+    /// with `radius` at its waist, widening by `radius` every `height` above
+    /// and below it, asymptotically.
+    pub fn hyperboloid(center: Point3D, radius: f32, height: f32, material: Box<dyn Material>) -> Self {
+        // ((x - x0)² + (z - z0)²) / r² - (y - y0)² / h² = 1
+        let k = Vec3(1.0 / (radius * radius), -1.0 / (height * height), 1.0 / (radius * radius));
+        let j = k.dot(center * center) - 1.0;
+        Quadric { coefficients: Quadric::centered(center, k, Vec3(0.0, 0.0, 0.0), j), clip: None, material }
+    }
+
+    /// The coefficients of kx (x - x0)² + ky (y - y0)² + kz (z - z0)² plus
+    /// `linear` · p, around `center` (x0, y0, z0), with the constant term `j`.
+    fn centered(center: Point3D, k: Vec3, linear: Vec3, j: f32) -> [f32; 10] {
+        [
+            k.x(), k.y(), k.z(), 0.0, 0.0, 0.0,
+            linear.x() - 2.0 * k.x() * center.x(),
+            linear.y() - 2.0 * k.y() * center.y(),
+            linear.z() - 2.0 * k.z() * center.z(),
+            j
+        ]
+    }
+
+    /// The gradient of the left side at `p`, which points outwards.
+    fn gradient(&self, p: Point3D) -> Vec3 {
+        let [a, b, c, d, e, f, g, h, i, _] = self.coefficients;
+        let (x, y, z) = (p.x(), p.y(), p.z());
+        Vec3(2.0 * a * x + d * y + e * z + g, 2.0 * b * y + d * x + f * z + h, 2.0 * c * z + e * x + f * y + i)
+    }
+}
+
+impl Hittable for Quadric {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let (t_min, t_max) = match self.clip {
+            Some(clip) => clip.clip(r, t_min, t_max)?,
+            None => (t_min, t_max)
+        };
+
+        let [a, b, c, d, e, f, g, h, i, j] = self.coefficients.map(|k| k as f64);
+        let (ox, oy, oz) = (r.origin().x() as f64, r.origin().y() as f64, r.origin().z() as f64);
+        let (dx, dy, dz) = (r.direction().x() as f64, r.direction().y() as f64, r.direction().z() as f64);
+
+        // The quadric at o + t d is a quadratic in t.
+        let qa = a * dx * dx + b * dy * dy + c * dz * dz + d * dx * dy + e * dx * dz + f * dy * dz;
+        let qb = 2.0 * (a * ox * dx + b * oy * dy + c * oz * dz)
+            + d * (ox * dy + oy * dx) + e * (ox * dz + oz * dx) + f * (oy * dz + oz * dy)
+            + g * dx + h * dy + i * dz;
+        let qc = a * ox * ox + b * oy * oy + c * oz * oz + d * ox * oy + e * ox * oz + f * oy * oz
+            + g * ox + h * oy + i * oz + j;
+
+        let t = polynomial::solve_quadratic(qa, qb, qc)
+            .into_iter()
+            .map(|t| t as f32)
+            .find(|&t| t >= t_min && t <= t_max)?;
+
+        let outward_normal = self.gradient(r.at(t)).unit_vector();
+        let (u, v) = get_sphere_uv(outward_normal);
+        let dpdu = Vec3(outward_normal.z(), 0.0, -outward_normal.x());
+        Some(HitRecord::new(r, t, outward_normal, u, v, dpdu, &*self.material))
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        Some(self.clip.unwrap_or_else(Aabb::infinite))
+    }
+}
@@ -0,0 +1,77 @@
+use crate::vec3::{Vec3, Color, Point3D};
+use crate::ray::Ray;
+use crate::hit::{Hittable, HittableList};
+use crate::background::Background;
+use crate::pdf::{self, Pdf, HittablePdf, BackgroundPdf, MixturePdf};
+use crate::medium::HomogeneousMedium;
+
+/// Everything `ray_color` needs to know about what is being rendered.
+pub struct Scene {
+    pub world: HittableList,
+    /// Shapes to sample explicitly. They should overlap emissive objects in `world`.
+    pub lights: HittableList,
+    pub background: Background
+}
+
+impl Scene {
+    /// The density used to sample light sources from `origin`: the `lights`,
+    /// the background, or an even mixture of both. `None` when nothing emits.
+    pub fn light_pdf(&self, origin: Point3D) -> Option<Box<dyn Pdf + '_>> {
+        let lights: Option<Box<dyn Pdf>> = if self.lights.is_empty() {
+            None
+        } else {
+            Some(Box::new(HittablePdf { origin, hittable: &self.lights }))
+        };
+        let background: Option<Box<dyn Pdf>> = if self.background.emits() {
+            Some(Box::new(BackgroundPdf { background: &self.background }))
+        } else {
+            None
+        };
+
+        match (lights, background) {
+            (Some(p0), Some(p1)) => Some(Box::new(MixturePdf { p0, p1 })),
+            (Some(p), None) | (None, Some(p)) => Some(p),
+            (None, None) => None
+        }
+    }
+
+    /// The light arriving along `r` without any further bounce: the emission
+    /// of the first surface hit, or the background, converted with `spectral`.
+    ///
+    /// `r` starts in `medium`, and crosses the boundaries of media on its way.
+    /// The light is attenuated by the media it travels through.
+    pub fn emission<F>(&self, r: Ray, medium: Option<HomogeneousMedium>, spectral: F) -> Color
+    where
+        F: Fn(Color) -> Color
+    {
+        let mut r = r;
+        let mut medium = medium;
+        let mut transmittance = Vec3(1.0, 1.0, 1.0);
+        loop {
+            let record = match (self.world.hit(r, 0.001, f32::INFINITY), medium) {
+                (Some(record), _) => record,
+                (None, None) => return transmittance * spectral(self.background.value(r)),
+                (None, Some(_)) => return Vec3(0.0, 0.0, 0.0)
+            };
+            if let Some(medium) = medium {
+                transmittance = transmittance * medium.transmittance(record.t * r.direction().length());
+            }
+            if !record.material.is_interface() {
+                return transmittance * spectral(record.material.emitted(r, record));
+            }
+            medium = if record.front_face { record.material.medium().map(|m| m.map(&spectral)) } else { None };
+            r = Ray(record.p, r.direction(), r.time(), r.wavelength());
+        }
+    }
+
+    /// Weight of the light found along `r`, when `r` was drawn from a material
+    /// density `bsdf_pdf`, against the chance of light sampling finding it too.
+    /// Rays that did not come from a density (camera and specular rays) can
+    /// not be light sampled, and get the full weight.
+    pub fn mis_weight(&self, r: Ray, bsdf_pdf: Option<f32>) -> f32 {
+        match (bsdf_pdf, self.light_pdf(r.origin())) {
+            (Some(bsdf_pdf), Some(light_pdf)) => pdf::power_heuristic(bsdf_pdf, light_pdf.value(r.direction())),
+            _ => 1.0
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use crate::vec3::{Vec3, Point3D};
+use crate::ray::Ray;
+use crate::hit::{HitRecord, Hittable};
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::sphere::get_sphere_uv;
+
+/// Sphere tracing stops when it is this close to the surface.
+const SURFACE_DISTANCE: f32 = 1e-4;
+/// Sphere tracing gives up after this many steps, which happens on rays
+/// grazing the surface for a long way.
+const MAX_STEPS: u32 = 512;
+
+/// A shape given by the signed distance from any point to its surface:
+/// positive outside, negative inside.
+pub trait Sdf {
+    fn distance(&self, p: Point3D) -> f32;
+
+    /// How much faster than the true distance `distance` may change between
+    /// two points, at most. Exact distances have 1, and shapes distorted by
+    /// stretching space have more. Sphere tracing divides its steps by it, so
+    /// that it never steps through the surface.
+    fn lipschitz(&self) -> f32 {
+        1.0
+    }
+}
+
+/// A sphere of `radius` around `center`.
+pub struct SdfSphere {
+    pub center: Point3D,
+    pub radius: f32
+}
+
+/// A box around `center`, reaching `half_size` from it along each axis, with
+/// edges and corners rounded with `radius`.
+pub struct RoundBox {
+    pub center: Point3D,
+    pub half_size: Vec3,
+    pub radius: f32
+}
+
+/// The Mandelbulb fractal of `power`, from `iterations` of its formula, around
+/// `center` and `scale` times larger than its usual size of about 1.1.
+/// Its distance is an estimate, from the growth of the derivative.
+pub struct Mandelbulb {
+    pub center: Point3D,
+    pub scale: f32,
+    pub power: f32,
+    pub iterations: u32
+}
+
+/// `a` and `b` blended into one another over a distance of about
+/// `smoothness`, with the polynomial smooth minimum.
+pub struct SmoothUnion {
+    pub a: Box<dyn Sdf>,
+    pub b: Box<dyn Sdf>,
+    pub smoothness: f32
+}
+
+/// `sdf` twisted around the y axis, by `rate` radians per unit of height.
+/// `radius` is how far from the axis the shape reaches, which bounds how
+/// much the twist stretches space.
+pub struct Twist {
+    pub sdf: Box<dyn Sdf>,
+    pub rate: f32,
+    pub radius: f32
+}
+
+/// Copies of `sdf`, which should be centered on the origin, every `period`
+/// along each axis. A period of 0 does not repeat along its axis. Each copy
+/// should fit in its cell, or distances are overestimated.
+pub struct Repeat {
+    pub sdf: Box<dyn Sdf>,
+    pub period: Vec3
+}
+
+/// A hittable shape defined by `sdf`, within `bounds`, which also limit
+/// infinite shapes like repetitions.
+///
+/// Hits are found by sphere tracing: stepping along the ray by the distance to
+/// the surface, which can not be crossed in one step. Normals are the gradient
+/// of the distance, and surface coordinates are mapped from their direction
+/// like on a sphere.
+pub struct SdfObject {
+    pub sdf: Box<dyn Sdf>,
+    pub bounds: Aabb,
+    pub material: Box<dyn Material>
+}
+
+fn abs(v: Vec3) -> Vec3 {
+    Vec3(v.x().abs(), v.y().abs(), v.z().abs())
+}
+
+fn max(v: Vec3, m: f32) -> Vec3 {
+    Vec3(v.x().max(m), v.y().max(m), v.z().max(m))
+}
+
+impl Sdf for SdfSphere {
+    fn distance(&self, p: Point3D) -> f32 {
+        (p - self.center).length() - self.radius
+    }
+}
+
+impl Sdf for RoundBox {
+    fn distance(&self, p: Point3D) -> f32 {
+        let radius = Vec3(self.radius, self.radius, self.radius);
+        let q = abs(p - self.center) - self.half_size + radius;
+        max(q, 0.0).length() + q.max_component().min(0.0) - self.radius
+    }
+}
+
+impl Sdf for Mandelbulb {
+    fn distance(&self, p: Point3D) -> f32 {
+        let c = (p - self.center) / self.scale;
+        let mut z = c;
+        let mut dr = 1.0;
+        let mut r = z.length();
+        for _ in 0..self.iterations {
+            if r > 2.0 {
+                break;
+            }
+            // z ← z^power + c, in spherical coordinates.
+            let theta = f32::acos((z.z() / r).clamp(-1.0, 1.0)) * self.power;
+            let phi = f32::atan2(z.y(), z.x()) * self.power;
+            dr = r.powf(self.power - 1.0) * self.power * dr + 1.0;
+            z = Vec3(theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos()) * r.powf(self.power) + c;
+            r = z.length();
+        }
+        if r == 0.0 {
+            return -self.scale;
+        }
+        0.5 * r.ln() * r / dr * self.scale
+    }
+}
+
+impl Sdf for SmoothUnion {
+    fn distance(&self, p: Point3D) -> f32 {
+        let (a, b) = (self.a.distance(p), self.b.distance(p));
+        let h = (0.5 + 0.5 * (b - a) / self.smoothness).clamp(0.0, 1.0);
+        b + (a - b) * h - self.smoothness * h * (1.0 - h)
+    }
+
+    fn lipschitz(&self) -> f32 {
+        self.a.lipschitz().max(self.b.lipschitz())
+    }
+}
+
+impl Sdf for Twist {
+    fn distance(&self, p: Point3D) -> f32 {
+        let (sin, cos) = f32::sin_cos(self.rate * p.y());
+        self.sdf.distance(Vec3(cos * p.x() - sin * p.z(), p.y(), sin * p.x() + cos * p.z()))
+    }
+
+    fn lipschitz(&self) -> f32 {
+        self.sdf.lipschitz() * f32::sqrt(1.0 + (self.rate * self.radius).powi(2))
+    }
+}
+
+impl Sdf for Repeat {
+    fn distance(&self, p: Point3D) -> f32 {
+        let repeat = |x: f32, period: f32| if period == 0.0 { x } else { x - period * (x / period).round() };
+        self.sdf.distance(Vec3(repeat(p.x(), self.period.x()), repeat(p.y(), self.period.y()), repeat(p.z(), self.period.z())))
+    }
+
+    fn lipschitz(&self) -> f32 {
+        self.sdf.lipschitz()
+    }
+}
+
+impl SdfObject {
+    /// The unit gradient of the distance at `p`, by central differences.
+    fn normal(&self, p: Point3D) -> Vec3 {
+        let h = SURFACE_DISTANCE;
+        let d = |offset: Vec3| self.sdf.distance(p + offset) - self.sdf.distance(p - offset);
+        Vec3(d(Vec3(h, 0.0, 0.0)), d(Vec3(0.0, h, 0.0)), d(Vec3(0.0, 0.0, h))).unit_vector()
+    }
+}
+
+impl Hittable for SdfObject {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let (t_start, t_end) = self.bounds.clip(r, t_min, t_max)?;
+        let speed = r.direction().length() * self.sdf.lipschitz();
+
+        // Distances are measured positive on the side the ray starts from, so
+        // that rays going through the inside, like refracted rays, work too.
+        let side = self.sdf.distance(r.at(t_start)).signum();
+        // Rays leaving a surface first have to get away from it, or they would
+        // hit it again where they start.
+        let mut away = false;
+        let mut t = t_start;
+        for _ in 0..MAX_STEPS {
+            let distance = side * self.sdf.distance(r.at(t));
+            if distance < SURFACE_DISTANCE {
+                if away {
+                    let outward_normal = self.normal(r.at(t));
+                    let (u, v) = get_sphere_uv(outward_normal);
+                    let dpdu = Vec3(outward_normal.z(), 0.0, -outward_normal.x());
+                    return Some(HitRecord::new(r, t, outward_normal, u, v, dpdu, &*self.material));
+                }
+            } else {
+                away = true;
+            }
+            t += distance.max(SURFACE_DISTANCE) / speed;
+            if t > t_end {
+                return None;
+            }
+        }
+        None
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        Some(self.bounds)
+    }
+}
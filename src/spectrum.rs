@@ -0,0 +1,100 @@
+use crate::vec3::{Vec3, Color};
+
+/// Range of wavelengths, in nanometers, sampled in spectral mode.
+pub const LAMBDA_MIN: f32 = 380.0;
+pub const LAMBDA_MAX: f32 = 720.0;
+
+/// Integral of the CIE ȳ matching function over the sampled range, so that a
+/// spectrum of constant 1 has luminance Y = 1.
+const CIE_Y_INTEGRAL: f32 = 106.911_87;
+
+/// Linear sRGB of a spectrum of constant 1 over the sampled range. Colors are
+/// divided by it, so that RGB white upsampled to a spectrum renders white again.
+const EQUAL_ENERGY_RGB: Color = Vec3(1.200_606_5, 0.949_637_5, 0.907_908_2);
+
+/// Smits' spectra for upsampling RGB reflectances, over 10 equal bins
+/// between `LAMBDA_MIN` and `LAMBDA_MAX`.
+const WHITE: [f32; 10] = [1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000];
+const CYAN: [f32; 10] = [0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000];
+const MAGENTA: [f32; 10] = [1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959];
+const YELLOW: [f32; 10] = [0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840];
+const RED: [f32; 10] = [0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149];
+const GREEN: [f32; 10] = [0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025];
+const BLUE: [f32; 10] = [1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496];
+
+/// Maps a uniform random number `u` in [0, 1) to a wavelength, uniformly.
+pub fn sample_wavelength(u: f32) -> f32 {
+    LAMBDA_MIN + u * (LAMBDA_MAX - LAMBDA_MIN)
+}
+
+/// Density of `sample_wavelength`.
+pub fn wavelength_pdf() -> f32 {
+    1.0 / (LAMBDA_MAX - LAMBDA_MIN)
+}
+
+/// The value at `wavelength` of a smooth spectrum that has RGB color `c`,
+/// with Smits' method: `c` is split into white, then a secondary color
+/// (cyan, magenta or yellow), then a primary color, each with a known spectrum.
+///
+/// The value is replicated on the three channels, so that the result can be
+/// multiplied with other colors as usual.
+pub fn upsample(c: Color, wavelength: f32) -> Color {
+    let bin = (((wavelength - LAMBDA_MIN) / (LAMBDA_MAX - LAMBDA_MIN) * 10.0) as usize).min(9);
+    let (r, g, b) = (c.x(), c.y(), c.z());
+
+    let value = if r <= g && r <= b {
+        r * WHITE[bin] + if g <= b {
+            (g - r) * CYAN[bin] + (b - g) * BLUE[bin]
+        } else {
+            (b - r) * CYAN[bin] + (g - b) * GREEN[bin]
+        }
+    } else if g <= r && g <= b {
+        g * WHITE[bin] + if r <= b {
+            (r - g) * MAGENTA[bin] + (b - r) * BLUE[bin]
+        } else {
+            (b - g) * MAGENTA[bin] + (r - b) * RED[bin]
+        }
+    } else {
+        b * WHITE[bin] + if r <= g {
+            (r - b) * YELLOW[bin] + (g - r) * GREEN[bin]
+        } else {
+            (g - b) * YELLOW[bin] + (r - g) * RED[bin]
+        }
+    };
+    Vec3(value, value, value)
+}
+
+/// A lobe of the piecewise Gaussian fit of the CIE matching functions,
+/// with different widths on each side of its mean.
+fn gaussian(wavelength: f32, mean: f32, sigma_left: f32, sigma_right: f32) -> f32 {
+    let sigma = if wavelength < mean { sigma_left } else { sigma_right };
+    f32::exp(-0.5 * ((wavelength - mean) / sigma).powi(2))
+}
+
+/// The CIE 1931 matching functions x̄, ȳ, z̄ at `wavelength`, with the
+/// multi-lobe fit of Wyman, Sloan and Shirley.
+pub fn cie_xyz(wavelength: f32) -> Vec3 {
+    let x = 1.056 * gaussian(wavelength, 599.8, 37.9, 31.0)
+        + 0.362 * gaussian(wavelength, 442.0, 16.0, 26.7)
+        - 0.065 * gaussian(wavelength, 501.1, 20.4, 26.2);
+    let y = 0.821 * gaussian(wavelength, 568.8, 46.9, 40.5)
+        + 0.286 * gaussian(wavelength, 530.9, 16.3, 31.1);
+    let z = 1.217 * gaussian(wavelength, 437.0, 11.8, 36.0)
+        + 0.681 * gaussian(wavelength, 459.0, 26.0, 13.8);
+    Vec3(x, y, z)
+}
+
+/// The CIE XYZ estimate of a radiance `radiance` carried at `wavelength`,
+/// which was sampled with `wavelength_pdf`.
+pub fn to_xyz(radiance: f32, wavelength: f32) -> Vec3 {
+    cie_xyz(wavelength) * (radiance / (wavelength_pdf() * CIE_Y_INTEGRAL))
+}
+
+/// Converts CIE XYZ to linear sRGB, white balanced so that a spectrum of
+/// constant 1 is white.
+pub fn xyz_to_rgb(xyz: Vec3) -> Color {
+    let r = 3.240_454_2 * xyz.x() - 1.537_138_5 * xyz.y() - 0.498_531_4 * xyz.z();
+    let g = -0.969_266 * xyz.x() + 1.876_010_8 * xyz.y() + 0.041_556 * xyz.z();
+    let b = 0.055_643_4 * xyz.x() - 0.204_025_9 * xyz.y() + 1.057_225_2 * xyz.z();
+    Vec3(r / EQUAL_ENERGY_RGB.x(), g / EQUAL_ENERGY_RGB.y(), b / EQUAL_ENERGY_RGB.z())
+}
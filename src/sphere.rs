@@ -1,7 +1,9 @@
-use crate::vec3::Vec3;
+use crate::vec3::{self, Vec3, Point3D};
 use crate::ray::Ray;
-use crate::hit::{HitRecord, Hittable};
+use crate::hit::{HitRecord, Hittable, Span};
+use crate::aabb::Aabb;
 use crate::material::Material;
+use crate::onb::Onb;
 
 pub struct Sphere {
     pub center: Vec3,
@@ -9,6 +11,29 @@ pub struct Sphere {
     pub material: Box<dyn Material>
 }
 
+/// Returns the surface coordinates (u, v) of a point `p` on the unit sphere.
+///
+/// u is the angle around the Y axis from X=-1, and v the angle from Y=-1 to Y=+1,
+/// both normalized to [0, 1].
+pub fn get_sphere_uv(p: Vec3) -> (f32, f32) {
+    let theta = f32::acos(-p.y());
+    let phi = f32::atan2(-p.z(), p.x()) + std::f32::consts::PI;
+
+    (phi / (2.0 * std::f32::consts::PI), theta / std::f32::consts::PI)
+}
+
+impl Sphere {
+    /// The record of the hit at `t` along `r`, which is on the sphere.
+    fn record(&self, r: Ray, t: f32) -> HitRecord<'_> {
+        let outward_normal = (r.at(t) - self.center) / self.radius;
+        let (u, v) = get_sphere_uv(outward_normal);
+        // u turns around the Y axis.
+        let dpdu = Vec3(outward_normal.z(), 0.0, -outward_normal.x());
+
+        HitRecord::new(r, t, outward_normal, u, v, dpdu, &*self.material)
+    }
+}
+
 impl Hittable for Sphere {
     fn hit<'a>(&'a self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'a>> {
         let oc = r.origin() - self.center;
@@ -30,28 +55,43 @@ impl Hittable for Sphere {
             }
         }
         
-        let p = r.at(root);
-        let t = root;
-        // TODO in the book the following logic is implemented as a 
-        // setter in the HitRecord struct. Refactor if we
-        // need this logic in more places.
-        let outward_normal = (p - self.center) / self.radius;
-        let front_face = r.direction().dot(outward_normal) < 0.0;
-        let normal = if front_face { 
-            outward_normal 
-        }   else {
-            - outward_normal
-        };
-       
-        let material = &*self.material;
-        let record = HitRecord {
-            p,
-            normal,
-            front_face,
-            t,
-            material
-        };
-
-        return Some(record);
+        Some(self.record(r, root))
+    }
+
+    /// Both roots of the quadratic at once, rather than a walk from hit to hit.
+    fn spans(&self, r: Ray) -> Vec<Span<'_>> {
+        let oc = r.origin() - self.center;
+        let a = r.direction().length_squared();
+        let half_b = oc.dot(r.direction());
+        let c = oc.length_squared() - self.radius * self.radius;
+
+        let discriminant = half_b * half_b - a * c;
+        if discriminant <= 0.0 {
+            return vec![];
+        }
+        let sqrtd = discriminant.sqrt();
+        vec![Span { enter: self.record(r, (-half_b - sqrtd) / a), exit: self.record(r, (-half_b + sqrtd) / a) }]
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        Some(Aabb::around(self.center, Vec3(self.radius, self.radius, self.radius)))
+    }
+
+    /// Directions towards the sphere are sampled uniformly inside the cone
+    /// it subtends from `origin`, so the density is one over its solid angle.
+    fn pdf_value(&self, origin: Point3D, v: Vec3) -> f32 {
+        if self.hit(Ray(origin, v, 0.0, 0.0), 0.001, f32::INFINITY).is_none() {
+            return 0.0;
+        }
+
+        let solid_angle = 2.0 * std::f32::consts::PI * vec3::cone_one_minus_cos(self.radius, (self.center - origin).length_squared());
+        if solid_angle > 0.0 { 1.0 / solid_angle } else { 0.0 }
+    }
+
+    fn random(&self, origin: Point3D) -> Vec3 {
+        let direction = self.center - origin;
+        let distance_squared = direction.length_squared();
+        let uvw = Onb::build_from_w(direction);
+        uvw.local(vec3::random_to_sphere(self.radius, distance_squared))
     }
 }
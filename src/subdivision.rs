@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use crate::vec3::{Vec3, Point3D};
+use crate::camera::Camera;
+use crate::mesh::{PolygonMesh, edge_key};
+
+/// `mesh` refined by `levels` steps of Catmull–Clark subdivision. After the
+/// first step, every face is a quad, and the surface converges to a smooth
+/// limit surface.
+///
+/// Creases follow the semi-sharp rules of DeRose et al.: an edge of sharpness
+/// s is refined with the sharp rules for the first s levels, blended for the
+/// fractional part, and smooth after. Boundary edges are infinitely sharp,
+/// so open meshes keep their borders.
+pub fn subdivide(mesh: &PolygonMesh, levels: u32) -> PolygonMesh {
+    let mut mesh = mesh.clone();
+    for _ in 0..levels {
+        mesh = refine(&mesh, true);
+    }
+    mesh
+}
+
+/// `mesh` split `levels` times into quads, like `subdivide`, but without
+/// smoothing: the new vertices stay on the faces of `mesh`. It adds vertices
+/// for displacement without changing the shape.
+pub fn tessellate(mesh: &PolygonMesh, levels: u32) -> PolygonMesh {
+    let mut mesh = mesh.clone();
+    for _ in 0..levels {
+        mesh = refine(&mesh, false);
+    }
+    mesh
+}
+
+/// The level of subdivision of `mesh` picked from its size on screen: the
+/// number of levels after which its longest edge, in pixels of the image
+/// seen by `camera`, which is `image_width` pixels wide, is no longer than
+/// `pixels_per_edge`, up to `max_level`.
+///
+/// The level is for the whole mesh, and is not adaptive: parts far from the
+/// camera are refined as much as the closest ones.
+pub fn screen_space_level(mesh: &PolygonMesh, camera: &Camera, image_width: usize, pixels_per_edge: f32, max_level: u32) -> u32 {
+    let longest = mesh.edge_faces().keys()
+        .map(|&(a, b)| {
+            let (pa, pb) = (mesh.positions[a], mesh.positions[b]);
+            (pa - pb).length() / camera.pixel_size((pa + pb) / 2.0, image_width)
+        })
+        .fold(0.0, f32::max);
+    if !longest.is_finite() {
+        return max_level;
+    }
+    // Every level halves the edges.
+    let levels = (longest / pixels_per_edge).log2().ceil();
+    levels.clamp(0.0, max_level as f32) as u32
+}
+
+fn average(points: impl Iterator<Item = Point3D>) -> Point3D {
+    let (sum, count) = points.fold((Vec3(0.0, 0.0, 0.0), 0), |(sum, count), p| (sum + p, count + 1));
+    sum / count as f32
+}
+
+fn lerp(t: f32, a: Vec3, b: Vec3) -> Vec3 {
+    a * (1.0 - t) + b * t
+}
+
+/// One step of Catmull–Clark subdivision, or of plain splitting, without
+/// `smooth`, as if all edges and vertices were sharp. New vertices are the
+/// vertex points, then the edge points, then the face points.
+fn refine(mesh: &PolygonMesh, smooth: bool) -> PolygonMesh {
+    let positions = &mesh.positions;
+    let edge_faces = mesh.edge_faces();
+    let mut edges: Vec<(usize, usize)> = edge_faces.keys().copied().collect();
+    edges.sort_unstable();
+    let edge_index: HashMap<(usize, usize), usize> = edges.iter().enumerate().map(|(i, &e)| (e, i)).collect();
+    // Boundary edges, and edges shared by more than two faces, are sharp.
+    let sharpness = |e: (usize, usize)| if smooth && edge_faces[&e].len() == 2 { mesh.sharpness(e.0, e.1) } else { f32::INFINITY };
+
+    let face_points: Vec<Point3D> = mesh.faces.iter().map(|face| average(face.iter().map(|&v| positions[v]))).collect();
+
+    let edge_points: Vec<Point3D> = edges.iter().map(|&e| {
+        let midpoint = (positions[e.0] + positions[e.1]) / 2.0;
+        let s = sharpness(e);
+        if s >= 1.0 {
+            return midpoint;
+        }
+        let faces = &edge_faces[&e];
+        let smooth = (positions[e.0] + positions[e.1] + face_points[faces[0]] + face_points[faces[1]]) / 4.0;
+        lerp(s, smooth, midpoint)
+    }).collect();
+
+    // The edges and faces around each vertex.
+    let mut vertex_edges: Vec<Vec<(usize, usize)>> = vec![Vec::new(); positions.len()];
+    for &e in &edges {
+        vertex_edges[e.0].push(e);
+        vertex_edges[e.1].push(e);
+    }
+    let mut vertex_faces: Vec<Vec<usize>> = vec![Vec::new(); positions.len()];
+    for (f, face) in mesh.faces.iter().enumerate() {
+        for &v in face {
+            vertex_faces[v].push(f);
+        }
+    }
+
+    let vertex_points: Vec<Point3D> = (0..positions.len()).map(|v| {
+        let old = positions[v];
+        let incident = &vertex_edges[v];
+        let n = incident.len();
+        if n == 0 || !smooth {
+            return old;
+        }
+        let sharp: Vec<(usize, usize)> = incident.iter().copied().filter(|&e| sharpness(e) > 0.0).collect();
+
+        let smooth = || {
+            let q = average(vertex_faces[v].iter().map(|&f| face_points[f]));
+            let r = average(incident.iter().map(|&(a, b)| (positions[a] + positions[b]) / 2.0));
+            (q + r * 2.0 + old * (n as f32 - 3.0)) / n as f32
+        };
+        let sharp_rule = || {
+            if sharp.len() == 2 && n > 2 {
+                // A crease: the vertex moves along the curve of sharp edges.
+                let other = |e: (usize, usize)| if e.0 == v { positions[e.1] } else { positions[e.0] };
+                (other(sharp[0]) + old * 6.0 + other(sharp[1])) / 8.0
+            } else {
+                // A corner, where more than two creases meet, or the corner of
+                // an open sheet.
+                old
+            }
+        };
+
+        match sharp.len() {
+            0 | 1 => smooth(),
+            _ => {
+                let s = sharp.iter().map(|&e| sharpness(e)).sum::<f32>() / sharp.len() as f32;
+                if s >= 1.0 { sharp_rule() } else { lerp(s, smooth(), sharp_rule()) }
+            }
+        }
+    }).collect();
+
+    let edge_offset = positions.len();
+    let face_offset = edge_offset + edges.len();
+    let mut faces = Vec::new();
+    for (f, face) in mesh.faces.iter().enumerate() {
+        for (i, &v) in face.iter().enumerate() {
+            let next = face[(i + 1) % face.len()];
+            let previous = face[(i + face.len() - 1) % face.len()];
+            faces.push(vec![
+                v,
+                edge_offset + edge_index[&edge_key(v, next)],
+                face_offset + f,
+                edge_offset + edge_index[&edge_key(previous, v)]
+            ]);
+        }
+    }
+
+    // Both halves of a crease are one level less sharp when smoothing, and
+    // as sharp as before when only splitting, which does not change the shape.
+    let mut creases = HashMap::new();
+    for (&(a, b), &s) in &mesh.creases {
+        let s = if smooth { s - 1.0 } else { s };
+        if s > 0.0 {
+            if let Some(&e) = edge_index.get(&(a, b)) {
+                creases.insert(edge_key(a, edge_offset + e), s);
+                creases.insert(edge_key(b, edge_offset + e), s);
+            }
+        }
+    }
+
+    let mut positions = vertex_points;
+    positions.extend(edge_points);
+    positions.extend(face_points);
+    PolygonMesh { positions, faces, creases }
+}
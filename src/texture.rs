@@ -0,0 +1,193 @@
+use crate::vec3::{Vec3, Color, Point3D};
+use crate::perlin::Perlin;
+use std::rc::Rc;
+use std::io;
+use std::path::Path;
+
+/// A color that varies over a surface, looked up by the surface
+/// coordinates (`u`, `v`) or by the hit point `p`.
+///
+/// Scalar parameters, like roughness, are read from the first channel.
+pub trait Texture {
+    fn value(&self, u: f32, v: f32, p: Point3D) -> Color;
+}
+
+pub struct SolidColor {
+    pub color: Color
+}
+
+/// A constant scalar texture, for parameters like roughness.
+pub fn solid(value: f32) -> Box<dyn Texture> {
+    Box::new(SolidColor { color: Vec3(value, value, value) })
+}
+
+/// A texture multiplied by a constant color.
+pub struct ScaledTexture {
+    pub texture: Box<dyn Texture>,
+    pub scale: Color
+}
+
+/// One `channel` (0, 1 or 2) of a texture, multiplied by `scale` and
+/// replicated on all three channels. Used to unpack scalar parameters
+/// stored together in one image.
+pub struct ChannelTexture {
+    pub texture: Box<dyn Texture>,
+    pub channel: usize,
+    pub scale: f32
+}
+
+/// A 3D checker pattern, alternating between the `odd` and `even` textures.
+pub struct CheckerTexture {
+    pub odd: Box<dyn Texture>,
+    pub even: Box<dyn Texture>
+}
+
+/// Turbulent Perlin noise, in [0, 1] on all three channels, with features
+/// about 1 / `scale` wide. Used as a height texture for bump mapping.
+pub struct NoiseTexture {
+    pub noise: Perlin,
+    pub scale: f32
+}
+
+/// An image mapped on the surface coordinates, repeated outside [0, 1],
+/// and filtered bilinearly.
+/// Pixels are stored row by row, from the top, with channels in [0, 1].
+pub struct ImageTexture {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Color>
+}
+
+impl ImageTexture {
+    /// Reads a binary (P6) or plain (P3) PPM image, like the ones this
+    /// renderer writes.
+    pub fn load_ppm<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+        // The header is four whitespace separated fields, with comments
+        // from '#' to the end of the line.
+        let mut fields = Vec::new();
+        let mut i = 0;
+        while fields.len() < 4 {
+            while i < bytes.len() && (bytes[i].is_ascii_whitespace() || bytes[i] == b'#') {
+                if bytes[i] == b'#' {
+                    while i < bytes.len() && bytes[i] != b'\n' {
+                        i += 1;
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+            let start = i;
+            while i < bytes.len() && !bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            if start == i {
+                return Err(invalid("truncated PPM header"));
+            }
+            fields.push(String::from_utf8_lossy(&bytes[start..i]).into_owned());
+        }
+        let number = |field: &str| field.parse::<usize>().map_err(|_| invalid("invalid number in PPM header"));
+        let (width, height, max_value) = (number(&fields[1])?, number(&fields[2])?, number(&fields[3])? as f32);
+
+        let samples: Vec<f32> = match fields[0].as_str() {
+            // A single whitespace separates the header from the binary samples.
+            "P6" if max_value < 256.0 => bytes.get(i + 1..).unwrap_or(&[]).iter().map(|&b| b as f32).collect(),
+            "P3" => String::from_utf8_lossy(&bytes[i..])
+                .split_ascii_whitespace()
+                .map(|s| s.parse::<f32>().map_err(|_| invalid("invalid sample in PPM")))
+                .collect::<io::Result<_>>()?,
+            _ => return Err(invalid("unsupported PPM format"))
+        };
+        if samples.len() < width * height * 3 {
+            return Err(invalid("truncated PPM data"));
+        }
+
+        let pixels = samples.chunks(3)
+            .take(width * height)
+            .map(|c| Vec3(c[0], c[1], c[2]) / max_value)
+            .collect();
+        Ok(ImageTexture { width, height, pixels })
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f32, _v: f32, _p: Point3D) -> Color {
+        self.color
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f32, v: f32, p: Point3D) -> Color {
+        let sines = f32::sin(10.0 * p.x()) * f32::sin(10.0 * p.y()) * f32::sin(10.0 * p.z());
+        if sines < 0.0 {
+            self.odd.value(u, v, p)
+        } else {
+            self.even.value(u, v, p)
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f32, _v: f32, p: Point3D) -> Color {
+        let value = self.noise.turb(p * self.scale, 7).min(1.0);
+        Vec3(value, value, value)
+    }
+}
+
+impl ImageTexture {
+    /// The pixel at column `i` and row `j`, repeating the image.
+    fn pixel(&self, i: i64, j: i64) -> Color {
+        let i = i.rem_euclid(self.width as i64) as usize;
+        let j = j.rem_euclid(self.height as i64) as usize;
+        self.pixels[j * self.width + i]
+    }
+}
+
+impl Texture for ImageTexture {
+    /// Bilinear interpolation of the four pixels around (`u`, `v`).
+    fn value(&self, u: f32, v: f32, _p: Point3D) -> Color {
+        if self.pixels.is_empty() {
+            return Vec3(0.0, 1.0, 1.0);
+        }
+        // Flip v to image coordinates, where rows go down, with pixel
+        // centers at integer positions.
+        let x = u * self.width as f32 - 0.5;
+        let y = (1.0 - v) * self.height as f32 - 0.5;
+        let (i, j) = (x.floor(), y.floor());
+        let (s, t) = (x - i, y - j);
+        let (i, j) = (i as i64, j as i64);
+
+        self.pixel(i, j) * ((1.0 - s) * (1.0 - t))
+            + self.pixel(i + 1, j) * (s * (1.0 - t))
+            + self.pixel(i, j + 1) * ((1.0 - s) * t)
+            + self.pixel(i + 1, j + 1) * (s * t)
+    }
+}
+
+impl Texture for ScaledTexture {
+    fn value(&self, u: f32, v: f32, p: Point3D) -> Color {
+        self.texture.value(u, v, p) * self.scale
+    }
+}
+
+impl Texture for ChannelTexture {
+    fn value(&self, u: f32, v: f32, p: Point3D) -> Color {
+        let color = self.texture.value(u, v, p);
+        let value = match self.channel {
+            0 => color.x(),
+            1 => color.y(),
+            _ => color.z()
+        } * self.scale;
+        Vec3(value, value, value)
+    }
+}
+
+/// Textures shared by several parameters, like a packed
+/// metallic-roughness image.
+impl<T: Texture + ?Sized> Texture for Rc<T> {
+    fn value(&self, u: f32, v: f32, p: Point3D) -> Color {
+        (**self).value(u, v, p)
+    }
+}
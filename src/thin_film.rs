@@ -0,0 +1,165 @@
+use std::f32::consts::PI;
+use std::ops::{Add, Sub, Mul, Div};
+use crate::vec3::{Vec3, Color};
+use crate::ray::Ray;
+use crate::hit::HitRecord;
+use crate::material::{Material, Scatter, Dielectric, Metal};
+use crate::texture::Texture;
+use crate::spectrum;
+
+/// Wavelengths, in nanometers, standing for the red, green and blue channels
+/// when the film is rendered in RGB.
+const RGB_WAVELENGTHS: [f32; 3] = [630.0, 532.0, 465.0];
+
+/// What a thin film is deposited on.
+pub enum Substrate {
+    Dielectric(Dielectric),
+    /// A metal. Its albedo is turned into a complex index of refraction, so
+    /// that the film can change the phase of the light it reflects.
+    Metal(Metal)
+}
+
+/// A thin transparent film over a substrate, like a soap bubble, an
+/// anti-reflection coating or an oxide layer on a metal. Light reflected by
+/// both faces of the film interferes, with the Airy formula.
+///
+/// `thickness`, in nanometers, and the film's `index_of_refraction` are read
+/// from the first channel of their textures.
+pub struct ThinFilm {
+    pub substrate: Substrate,
+    pub thickness: Box<dyn Texture>,
+    pub index_of_refraction: Box<dyn Texture>
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Complex(f32, f32);
+
+impl Complex {
+    fn norm_squared(self) -> f32 {
+        self.0 * self.0 + self.1 * self.1
+    }
+
+    fn sqrt(self) -> Complex {
+        let r = self.norm_squared().sqrt();
+        let re = f32::sqrt(0.5 * (r + self.0).max(0.0));
+        let im = f32::sqrt(0.5 * (r - self.0).max(0.0));
+        Complex(re, if self.1 < 0.0 { -im } else { im })
+    }
+
+    /// e^{iθ}
+    fn unit(theta: f32) -> Complex {
+        Complex(theta.cos(), theta.sin())
+    }
+}
+
+impl From<f32> for Complex {
+    fn from(re: f32) -> Self {
+        Complex(re, 0.0)
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, o: Complex) -> Complex { Complex(self.0 + o.0, self.1 + o.1) }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, o: Complex) -> Complex { Complex(self.0 - o.0, self.1 - o.1) }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, o: Complex) -> Complex { Complex(self.0 * o.0 - self.1 * o.1, self.0 * o.1 + self.1 * o.0) }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+    fn div(self, o: Complex) -> Complex {
+        let d = o.norm_squared();
+        Complex((self.0 * o.0 + self.1 * o.1) / d, (self.1 * o.0 - self.0 * o.1) / d)
+    }
+}
+
+/// Reflectance of unpolarized light at `wavelength` off a film of index `n2`
+/// and `thickness` (in nanometers), lit from a medium of index `n1` at
+/// `cos_theta`, over a substrate of complex index `n3`.
+///
+/// The reflected amplitudes of both faces of the film, in the s and p
+/// polarizations, are summed over all internal bounces with the Airy formula.
+fn airy_reflectance(cos_theta: f32, n1: f32, n2: f32, n3: Complex, thickness: f32, wavelength: f32) -> f32 {
+    let sin2_theta = 1.0 - cos_theta * cos_theta;
+    let (n1, n2) = (Complex::from(n1), Complex::from(n2));
+    let cos1 = Complex::from(cos_theta);
+    // Snell's law, n1 sin θ1 = n2 sin θ2 = n3 sin θ3, with complex angles
+    // past total internal reflection or in a conductor.
+    let cos_in = |n: Complex| (Complex::from(1.0) - n1 * n1 * Complex::from(sin2_theta) / (n * n)).sqrt();
+    let (cos2, cos3) = (cos_in(n2), cos_in(n3));
+
+    let r12s = (n1 * cos1 - n2 * cos2) / (n1 * cos1 + n2 * cos2);
+    let r12p = (n2 * cos1 - n1 * cos2) / (n2 * cos1 + n1 * cos2);
+    let r23s = (n2 * cos2 - n3 * cos3) / (n2 * cos2 + n3 * cos3);
+    let r23p = (n3 * cos2 - n2 * cos3) / (n3 * cos2 + n2 * cos3);
+
+    // Phase difference between two consecutive reflections, of the optical
+    // path across the film and back.
+    let delta = Complex::from(4.0 * PI * thickness / wavelength) * n2 * cos2;
+    // e^{iδ}, damped when δ is complex.
+    let phase = Complex::unit(delta.0) * Complex::from(f32::exp(-delta.1));
+
+    let airy = |r12: Complex, r23: Complex| {
+        ((r12 + r23 * phase) / (Complex::from(1.0) + r12 * r23 * phase)).norm_squared()
+    };
+    (0.5 * (airy(r12s, r23s) + airy(r12p, r23p))).clamp(0.0, 1.0)
+}
+
+/// The reflectance of a film, per channel in RGB, or replicated on the three
+/// channels at the ray's `wavelength` in spectral mode. `substrate(λ)` is the
+/// index of the substrate at λ.
+fn film_reflectance<F>(cos_theta: f32, n1: f32, n2: f32, thickness: f32, wavelength: f32, substrate: F) -> Color
+where
+    F: Fn(f32) -> Complex
+{
+    let r = |wavelength: f32| airy_reflectance(cos_theta, n1, n2, substrate(wavelength), thickness, wavelength);
+    if wavelength > 0.0 {
+        let r = r(wavelength);
+        Vec3(r, r, r)
+    } else {
+        Vec3(r(RGB_WAVELENGTHS[0]), r(RGB_WAVELENGTHS[1]), r(RGB_WAVELENGTHS[2]))
+    }
+}
+
+/// A complex index of refraction with the reflectance `r` at normal incidence,
+/// from Gulbrandsen's artist friendly metallic Fresnel with an edge tint of
+/// 1: n = (1 - r) / (1 + r) and k = 2√r / (1 + r).
+fn metal_index(r: f32) -> Complex {
+    let r = r.clamp(0.0, 0.999);
+    Complex((1.0 - r) / (1.0 + r), 2.0 * r.sqrt() / (1.0 + r))
+}
+
+impl Material for ThinFilm {
+    fn scatter(&self, r_in: Ray, record: HitRecord) -> Option<Scatter> {
+        let thickness = self.thickness.value(record.u, record.v, record.p).x();
+        let film = self.index_of_refraction.value(record.u, record.v, record.p).x();
+        let wavelength = r_in.wavelength();
+
+        match &self.substrate {
+            // The film is on the outside: from inside the glass, light crosses
+            // the film to get out into the air.
+            Substrate::Dielectric(dielectric) => {
+                dielectric.scatter_with_reflectance(r_in, record, |cos_theta, n_incident, n_transmitted| {
+                    film_reflectance(cos_theta, n_incident, film, thickness, wavelength, |_| Complex::from(n_transmitted))
+                })
+            }
+            Substrate::Metal(metal) => {
+                let cos_theta = f32::min((-r_in.direction().unit_vector()).dot(record.normal), 1.0);
+                // The albedo is upsampled to a spectrum in both modes, so that
+                // the metal has an index at any wavelength.
+                let r = film_reflectance(cos_theta, 1.0, film, thickness, wavelength, |wavelength| {
+                    metal_index(spectrum::upsample(metal.albedo, wavelength).x())
+                });
+                metal.reflect(r_in, record, r)
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use crate::vec3::{Vec3, Point3D};
+use crate::ray::Ray;
+use crate::hit::{HitRecord, Hittable};
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::onb::Onb;
+use crate::polynomial;
+
+/// A torus around `center`: a tube of `minor_radius` around the circle of
+/// `major_radius` perpendicular to the unit `axis`. u turns around the axis,
+/// and v around the tube.
+pub struct Torus {
+    pub center: Point3D,
+    pub axis: Vec3,
+    pub major_radius: f32,
+    pub minor_radius: f32,
+    pub material: Box<dyn Material>
+}
+
+impl Hittable for Torus {
+    /// The ray is first clipped to the bounding box, and the quartic is solved
+    /// from where it enters the box: starting close to the torus keeps its
+    /// coefficients small, and its roots accurate.
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let (t_enter, t_exit) = self.bounding_box(r.time(), r.time())?.clip(r, t_min, t_max)?;
+
+        let frame = Onb::build_from_w(self.axis);
+        let o = frame.to_local(r.at(t_enter) - self.center);
+        let d = frame.to_local(r.direction());
+        let (ox, oy, oz) = (o.x() as f64, o.y() as f64, o.z() as f64);
+        let (dx, dy, dz) = (d.x() as f64, d.y() as f64, d.z() as f64);
+        let major2 = (self.major_radius * self.major_radius) as f64;
+        let minor2 = (self.minor_radius * self.minor_radius) as f64;
+
+        // (|p|² - R² - r²)² = 4R² (r² - z²), at p = o + d t.
+        let dd = dx * dx + dy * dy + dz * dz;
+        let e = ox * ox + oy * oy + oz * oz - major2 - minor2;
+        let f = ox * dx + oy * dy + oz * dz;
+        let roots = polynomial::solve_quartic(
+            dd * dd,
+            4.0 * dd * f,
+            2.0 * dd * e + 4.0 * f * f + 4.0 * major2 * dz * dz,
+            4.0 * f * e + 8.0 * major2 * oz * dz,
+            e * e - 4.0 * major2 * (minor2 - oz * oz)
+        );
+        let s = roots.into_iter().find(|&s| s >= 0.0 && s as f32 <= t_exit - t_enter)? as f32;
+
+        let p = o + d * s;
+        let ring_distance = f32::sqrt(p.x() * p.x() + p.y() * p.y());
+        let ring = Vec3(p.x(), p.y(), 0.0) * (self.major_radius / ring_distance);
+        let outward_normal = frame.local((p - ring) / self.minor_radius);
+
+        let tau = 2.0 * std::f32::consts::PI;
+        let u = (f32::atan2(p.y(), p.x()) + std::f32::consts::PI) / tau;
+        let v = (f32::atan2(p.z(), ring_distance - self.major_radius) + std::f32::consts::PI) / tau;
+        let dpdu = frame.local(Vec3(-p.y(), p.x(), 0.0));
+
+        Some(HitRecord::new(r, t_enter + s, outward_normal, u, v, dpdu, &*self.material))
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        let ring = Aabb::disk(self.center, self.axis.unit_vector(), self.major_radius);
+        let tube = Vec3(self.minor_radius, self.minor_radius, self.minor_radius);
+        Some(Aabb { minimum: ring.minimum - tube, maximum: ring.maximum + tube })
+    }
+}
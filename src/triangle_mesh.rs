@@ -0,0 +1,222 @@
+use std::rc::Rc;
+use crate::vec3::{Vec3, Point3D};
+use crate::ray::Ray;
+use crate::hit::{self, HitRecord, Hittable};
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::mesh::PolygonMesh;
+use crate::bvh::Bvh;
+
+/// A mesh of triangles, with a normal at each corner of each triangle that
+/// is interpolated across it for smooth shading. u and v are the
+/// barycentric coordinates of hits on their triangle.
+///
+/// Deforming meshes have the positions and normals of their vertices at
+/// several times, and are interpolated linearly between them at the time of
+/// rays, for motion blur.
+///
+/// The triangles are kept in a `Bvh`, so that large meshes are fast to hit.
+/// Its boxes contain the triangles over the whole motion.
+pub struct TriangleMesh {
+    bvh: Bvh
+}
+
+/// The data shared by all the triangles of a mesh. `positions` and `normals`
+/// hold a sample for each time, evenly spaced from `time_0` to `time_1`.
+struct MeshData {
+    positions: Vec<Vec<Point3D>>,
+    triangles: Vec<[usize; 3]>,
+    normals: Vec<Vec<[Vec3; 3]>>,
+    time_0: f32,
+    time_1: f32,
+    material: Box<dyn Material>
+}
+
+/// The triangle at `index` in `mesh`.
+struct Triangle {
+    mesh: Rc<MeshData>,
+    index: usize
+}
+
+/// Finds the set an element belongs to, for the union-find of corners.
+fn find(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+impl TriangleMesh {
+    /// A mesh of `triangles`, which index `positions` counterclockwise seen
+    /// from the outside, with the unit `normals` of their corners.
+    pub fn new(positions: Vec<Point3D>, triangles: Vec<[usize; 3]>, normals: Vec<[Vec3; 3]>, material: Box<dyn Material>) -> Self {
+        assert_eq!(triangles.len(), normals.len(), "every triangle needs corner normals");
+        TriangleMesh::build(MeshData { positions: vec![positions], triangles, normals: vec![normals], time_0: 0.0, time_1: 1.0, material })
+    }
+
+    fn build(mesh: MeshData) -> Self {
+        let mesh = Rc::new(mesh);
+        let triangles: Vec<Box<dyn Hittable>> = (0..mesh.triangles.len())
+            .map(|index| Box::new(Triangle { mesh: Rc::clone(&mesh), index }) as Box<dyn Hittable>)
+            .collect();
+        TriangleMesh { bvh: Bvh::new(triangles, mesh.time_0, mesh.time_1) }
+    }
+
+    /// `mesh` split into triangles, with smooth normals. The normal at a
+    /// corner averages the faces around its vertex that can be reached
+    /// without crossing a crease or a boundary, so that creases stay sharp.
+    pub fn from_polygons(mesh: &PolygonMesh, material: Box<dyn Material>) -> Self {
+        let (triangles, normals) = TriangleMesh::triangulate(mesh);
+        TriangleMesh::new(mesh.positions.clone(), triangles, normals, material)
+    }
+
+    /// A mesh deforming through `frames`, evenly spaced from `time_0` to
+    /// `time_1`, like `from_polygons`. The frames have the same vertices and
+    /// faces, and only their positions differ. The mesh stays at the first
+    /// frame before `time_0`, and at the last after `time_1`.
+    pub fn deforming(frames: &[PolygonMesh], time_0: f32, time_1: f32, material: Box<dyn Material>) -> Self {
+        assert!(!frames.is_empty(), "a deforming mesh needs frames");
+        assert!(frames.iter().all(|frame| frame.faces == frames[0].faces), "frames of a deforming mesh need the same faces");
+        let (triangles, _) = TriangleMesh::triangulate(&frames[0]);
+        let normals = frames.iter().map(|frame| TriangleMesh::triangulate(frame).1).collect();
+        let positions = frames.iter().map(|frame| frame.positions.clone()).collect();
+        TriangleMesh::build(MeshData { positions, triangles, normals, time_0, time_1, material })
+    }
+
+    /// The triangles of `mesh`, and the normals of their corners.
+    fn triangulate(mesh: &PolygonMesh) -> (Vec<[usize; 3]>, Vec<[Vec3; 3]>) {
+        let face_normals: Vec<Vec3> = (0..mesh.faces.len()).map(|f| mesh.face_normal(f)).collect();
+
+        // The corners of faces are grouped across smooth edges, and their
+        // normals weighted by the area of the faces.
+        let mut first_corner = Vec::with_capacity(mesh.faces.len());
+        let mut corners = 0;
+        for face in &mesh.faces {
+            first_corner.push(corners);
+            corners += face.len();
+        }
+        let corner = |f: usize, v: usize| first_corner[f] + mesh.faces[f].iter().position(|&w| w == v).unwrap();
+        let mut parents: Vec<usize> = (0..corners).collect();
+        for (&(a, b), faces) in &mesh.edge_faces() {
+            if faces.len() != 2 || mesh.sharpness(a, b) > 0.0 {
+                continue;
+            }
+            for v in [a, b] {
+                let (i, j) = (find(&mut parents, corner(faces[0], v)), find(&mut parents, corner(faces[1], v)));
+                parents[i] = j;
+            }
+        }
+
+        let mut sums = vec![Vec3(0.0, 0.0, 0.0); corners];
+        for (f, face) in mesh.faces.iter().enumerate() {
+            for i in 0..face.len() {
+                let root = find(&mut parents, first_corner[f] + i);
+                sums[root] = sums[root] + face_normals[f];
+            }
+        }
+        let mut corner_normals = Vec::with_capacity(corners);
+        for i in 0..corners {
+            let root = find(&mut parents, i);
+            corner_normals.push(sums[root].unit_vector());
+        }
+
+        // Faces are split into fans of triangles around their first vertex.
+        let mut triangles = Vec::new();
+        let mut normals = Vec::new();
+        for (f, face) in mesh.faces.iter().enumerate() {
+            let first = first_corner[f];
+            for i in 1..face.len() - 1 {
+                triangles.push([face[0], face[i], face[i + 1]]);
+                normals.push([corner_normals[first], corner_normals[first + i], corner_normals[first + i + 1]]);
+            }
+        }
+        (triangles, normals)
+    }
+}
+
+impl MeshData {
+    /// The samples before and after `time`, and how far `time` is between
+    /// them.
+    fn samples(&self, time: f32) -> (usize, usize, f32) {
+        let last = self.positions.len() - 1;
+        if last == 0 {
+            return (0, 0, 0.0);
+        }
+        let s = ((time - self.time_0) / (self.time_1 - self.time_0)).clamp(0.0, 1.0) * last as f32;
+        let before = (s.floor() as usize).min(last - 1);
+        (before, before + 1, s - before as f32)
+    }
+
+    /// The corners of triangle `index` at `time`.
+    fn corners(&self, index: usize, time: f32) -> [Point3D; 3] {
+        let (before, after, s) = self.samples(time);
+        self.triangles[index].map(|i| self.positions[before][i] * (1.0 - s) + self.positions[after][i] * s)
+    }
+
+    /// The unit normals at the corners of triangle `index` at `time`.
+    fn corner_normals(&self, index: usize, time: f32) -> [Vec3; 3] {
+        let (before, after, s) = self.samples(time);
+        let (n0, n1) = (self.normals[before][index], self.normals[after][index]);
+        [0, 1, 2].map(|i| (n0[i] * (1.0 - s) + n1[i] * s).unit_vector())
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        self.bvh.hit(r, t_min, t_max)
+    }
+
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb> {
+        self.bvh.bounding_box(time0, time1)
+    }
+}
+
+impl Hittable for Triangle {
+    /// Hits are found with the Möller-Trumbore algorithm.
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let [p0, p1, p2] = self.mesh.corners(self.index, r.time());
+        let (edge1, edge2) = (p1 - p0, p2 - p0);
+        let pvec = r.direction().cross(edge2);
+        let determinant = edge1.dot(pvec);
+        if determinant.abs() < 1e-12 {
+            return None;
+        }
+        let inv_determinant = 1.0 / determinant;
+        let tvec = r.origin() - p0;
+        let b1 = tvec.dot(pvec) * inv_determinant;
+        if !(0.0..=1.0).contains(&b1) {
+            return None;
+        }
+        let qvec = tvec.cross(edge1);
+        let b2 = r.direction().dot(qvec) * inv_determinant;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return None;
+        }
+        let t = edge2.dot(qvec) * inv_determinant;
+        if t < t_min || t > t_max {
+            return None;
+        }
+
+        let [n0, n1, n2] = self.mesh.corner_normals(self.index, r.time());
+        let smooth_normal = (n0 * (1.0 - b1 - b2) + n1 * b1 + n2 * b2).unit_vector();
+
+        // As for heightfields, the flat triangle decides which side is hit.
+        let record = HitRecord::new(r, t, edge1.cross(edge2).unit_vector(), b1, b2, edge1, &*self.mesh.material);
+        let normal = if record.front_face { smooth_normal } else { -smooth_normal };
+        let (tangent, bitangent) = hit::tangent_frame(normal, edge1);
+        Some(HitRecord { normal, tangent, bitangent, ..record })
+    }
+
+    /// The triangle moves linearly between samples, so it stays within the
+    /// box of its corners at the samples around [`time0`, `time1`].
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb> {
+        let mesh = &self.mesh;
+        let (first, _, _) = mesh.samples(time0);
+        let (_, last, _) = mesh.samples(time1);
+        let corners = (first..=last).flat_map(|sample| mesh.triangles[self.index].map(|i| mesh.positions[sample][i]));
+        // Padded, so that triangles lying in an axis plane have a volume.
+        let padding = Vec3(1e-4, 1e-4, 1e-4);
+        corners.map(|p| Aabb::around(p, padding)).reduce(Aabb::surrounding_box)
+    }
+}
@@ -25,20 +25,49 @@ pub fn random_in_unit_sphere() -> Vec3 {
     }
 }
 
-// True Lambertian Reflection. Generates more uniform rays of light.
+// Uniformly distributed direction on the unit sphere.
 pub fn random_unit_vector() -> Vec3 {
     Vec3::unit_vector(random_in_unit_sphere())
 }
 
-// Naive method; reflection does not depend on the angle from normal.
-// scatter direction if uniform for all angles away from the hit point,
-pub fn random_in_hemisphere(normal: Vec3) -> Vec3 {
-    let in_unit_sphere = random_in_unit_sphere();
+// Cosine weighted direction around the z axis.
+pub fn random_cosine_direction() -> Vec3 {
+    let r1 = random_double(None, None);
+    let r2 = random_double(None, None);
+    let z = f32::sqrt(1.0 - r2);
 
-    if in_unit_sphere.dot(normal) > 0.0 { // In the same hemisphere as the normal
-        return in_unit_sphere;
-    }
-    return -in_unit_sphere
+    let phi = 2.0 * std::f32::consts::PI * r1;
+    let x = f32::cos(phi) * f32::sqrt(r2);
+    let y = f32::sin(phi) * f32::sqrt(r2);
+
+    Vec3(x, y, z)
+}
+
+/// 1 - cos θmax, where θmax is the half angle of the cone subtended by a
+/// sphere of `radius` at squared distance `distance_squared`. It is computed
+/// as x / (1 + sqrt(1 - x)), with x = radius² / distance², which does not
+/// round to 0 for far spheres as 1 - sqrt(1 - x) does. From inside the
+/// sphere, the cone is every direction, and it is 2.
+pub fn cone_one_minus_cos(radius: f32, distance_squared: f32) -> f32 {
+    let x = radius * radius / distance_squared;
+    if x >= 1.0 {
+        return 2.0;
+    }
+    x / (1.0 + f32::sqrt(1.0 - x))
+}
+
+// Uniform direction around the z axis, within the cone subtended by a sphere
+// of `radius` at squared distance `distance_squared`.
+pub fn random_to_sphere(radius: f32, distance_squared: f32) -> Vec3 {
+    let r1 = random_double(None, None);
+    let r2 = random_double(None, None);
+    let z = 1.0 - r2 * cone_one_minus_cos(radius, distance_squared);
+
+    let phi = 2.0 * std::f32::consts::PI * r1;
+    let x = f32::cos(phi) * f32::sqrt((1.0 - z * z).max(0.0));
+    let y = f32::sin(phi) * f32::sqrt((1.0 - z * z).max(0.0));
+
+    Vec3(x, y, z)
 }
 
 pub fn random_in_unit_disk() -> Vec3 {
@@ -76,13 +105,17 @@ impl Vec3 {
              self.0 * v.1 - self.1 * v.0)
     }
 
-    pub fn unit_vector(self) -> Vec3 {
-        self / self.length()
+    /// Whether no component is infinite or NaN.
+    pub fn is_finite(self) -> bool {
+        self.0.is_finite() && self.1.is_finite() && self.2.is_finite()
     }
 
-    pub fn near_zero(self) -> bool {
-        let s = 1e-8;
-        return f32::abs(self.0) < s && f32::abs(self.1) < s && f32::abs(self.2) < s 
+    pub fn max_component(self) -> f32 {
+        f32::max(self.0, f32::max(self.1, self.2))
+    }
+
+    pub fn unit_vector(self) -> Vec3 {
+        self / self.length()
     }
 
     pub fn reflect(self, n: Vec3) -> Vec3 {